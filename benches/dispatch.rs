@@ -0,0 +1,77 @@
+//! Measures how many instructions per second each engine executes on a
+//! mix of arithmetic, memory, branch and subroutine instructions.
+//!
+//! Run with `cargo bench`.
+
+use lc3_emu::assembler::assemble;
+use lc3_emu::{BufferConsole, Emulator, Engine};
+
+use std::time::Instant;
+
+const PROGRAM: &str = "
+        .ORIG x3000
+        LD R6, COUNT
+OUTER   LEA R1, DATA
+        AND R2, R2, #0
+        ADD R3, R2, #8
+INNER   LDR R4, R1, #0
+        ADD R2, R2, R4
+        NOT R4, R4
+        STR R4, R1, #0
+        ADD R1, R1, #1
+        ADD R3, R3, #-1
+        BRp INNER
+        JSR SUB
+        ST R2, TOTAL
+        ADD R6, R6, #-1
+        BRp OUTER
+        HALT
+SUB     LDI R5, TOTAL_P
+        AND R5, R5, R2
+        RET
+COUNT   .FILL x7FFF
+TOTAL   .FILL x0000
+TOTAL_P .FILL TOTAL
+DATA    .FILL 1
+        .FILL 2
+        .FILL 3
+        .FILL 4
+        .FILL 5
+        .FILL 6
+        .FILL 7
+        .FILL 8
+        .END
+";
+
+const RUNS: usize = 5;
+
+fn load(program: &[u8], engine: Engine) -> Emulator {
+    let mut emu = Emulator::new();
+    emu.set_console(BufferConsole::new(b""));
+    emu.engine = engine;
+    emu.load_image(program).unwrap();
+    emu
+}
+
+fn main() {
+    let program = assemble(PROGRAM).unwrap().to_object_bytes();
+
+    let mut emu = load(&program, Engine::Interpreter);
+    let mut executed: u64 = 0;
+    while emu.running {
+        emu.step().unwrap();
+        executed += 1;
+    }
+    println!("{} instructions per run", executed);
+
+    for engine in [Engine::Interpreter, Engine::Block] {
+        let mut best = 0.0f64;
+        for _ in 0..RUNS {
+            let mut emu = load(&program, engine);
+            let start = Instant::now();
+            emu.run().unwrap();
+            best = best.max(executed as f64 / start.elapsed().as_secs_f64());
+        }
+        println!("{:?}: {:.1} M instructions/s", engine, best / 1e6);
+    }
+}
@@ -0,0 +1,595 @@
+use crate::emulator::MEMORY_MAX;
+use crate::opcode::{Opcode, Trap};
+use crate::symbol::SymbolTable;
+
+use std::fmt;
+
+/// An assembly failure, pointing at the offending source line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AsmError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl AsmError {
+    fn new(line: usize, message: impl Into<String>) -> Self {
+        AsmError {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for AsmError {}
+
+/// The output of a successful assembly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Program {
+    pub origin: u16,
+    pub words: Vec<u16>,
+    pub symbols: SymbolTable,
+}
+
+impl Program {
+    /// Serializes the program as a big-endian `.obj` image: the origin
+    /// followed by every word of the program.
+    pub fn to_object_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(2 * (self.words.len() + 1));
+        bytes.extend_from_slice(&self.origin.to_be_bytes());
+        for word in &self.words {
+            bytes.extend_from_slice(&word.to_be_bytes());
+        }
+        bytes
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    Str(String),
+}
+
+struct Statement {
+    line: usize,
+    label: Option<String>,
+    mnemonic: Option<String>,
+    operands: Vec<Token>,
+}
+
+/// Assembles LC-3 source text into a program image and its symbol table.
+pub fn assemble(source: &str) -> Result<Program, AsmError> {
+    let mut statements = Vec::new();
+    for (i, text) in source.lines().enumerate() {
+        if let Some(statement) = parse_line(i + 1, text)? {
+            statements.push(statement);
+        }
+    }
+
+    // First pass: find the origin and assign an address to every label.
+    let mut origin: Option<u16> = None;
+    let mut symbols = SymbolTable::new();
+    let mut address: usize = 0;
+    let mut body = Vec::new();
+
+    for statement in statements {
+        let mnemonic = statement.mnemonic.as_deref();
+
+        if origin.is_none() {
+            match mnemonic {
+                Some(".ORIG") => {
+                    if statement.label.is_some() {
+                        return Err(AsmError::new(statement.line, ".ORIG cannot be labelled"));
+                    }
+                    expect_operands(&statement, 1)?;
+                    let value = number_operand(&statement, 0)?;
+                    if !(0..MEMORY_MAX as i32).contains(&value) {
+                        return Err(AsmError::new(statement.line, "origin out of range"));
+                    }
+                    origin = Some(value as u16);
+                    address = value as usize;
+                    continue;
+                }
+                _ => {
+                    return Err(AsmError::new(
+                        statement.line,
+                        "expected .ORIG before any other statement",
+                    ))
+                }
+            }
+        }
+
+        if mnemonic == Some(".END") {
+            break;
+        }
+        if mnemonic == Some(".ORIG") {
+            return Err(AsmError::new(statement.line, "duplicate .ORIG"));
+        }
+
+        if let Some(label) = &statement.label {
+            if !symbols.insert(label, address as u16) {
+                return Err(AsmError::new(
+                    statement.line,
+                    format!("duplicate label '{}'", label),
+                ));
+            }
+        }
+
+        address += statement_size(&statement)?;
+        if address > MEMORY_MAX {
+            return Err(AsmError::new(statement.line, "program exceeds memory"));
+        }
+        body.push(statement);
+    }
+
+    let origin = origin.ok_or_else(|| AsmError::new(0, "missing .ORIG"))?;
+
+    // Second pass: encode every statement now that all labels are known.
+    let mut words = Vec::new();
+    for statement in &body {
+        let pc = origin as usize + words.len();
+        encode_statement(statement, pc as u16, &symbols, &mut words)?;
+    }
+
+    Ok(Program {
+        origin,
+        words,
+        symbols,
+    })
+}
+
+fn tokenize(line: usize, text: &str) -> Result<Vec<Token>, AsmError> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() || c == ',' {
+            chars.next();
+        } else if c == ';' {
+            break;
+        } else if c == '"' {
+            chars.next();
+            let mut s = String::new();
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') => match chars.next() {
+                        Some('n') => s.push('\n'),
+                        Some('t') => s.push('\t'),
+                        Some('r') => s.push('\r'),
+                        Some('0') => s.push('\0'),
+                        Some('e') => s.push('\x1b'),
+                        Some('"') => s.push('"'),
+                        Some('\\') => s.push('\\'),
+                        Some(other) => {
+                            return Err(AsmError::new(
+                                line,
+                                format!("unknown escape sequence '\\{}'", other),
+                            ))
+                        }
+                        None => return Err(AsmError::new(line, "unterminated string")),
+                    },
+                    Some(other) => s.push(other),
+                    None => return Err(AsmError::new(line, "unterminated string")),
+                }
+            }
+            tokens.push(Token::Str(s));
+        } else {
+            let mut word = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() || c == ',' || c == ';' || c == '"' {
+                    break;
+                }
+                word.push(c);
+                chars.next();
+            }
+            tokens.push(Token::Word(word));
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn parse_line(line: usize, text: &str) -> Result<Option<Statement>, AsmError> {
+    let mut tokens = tokenize(line, text)?.into_iter().peekable();
+
+    let mut label = None;
+    if let Some(Token::Word(word)) = tokens.peek() {
+        if !is_mnemonic(&word.to_uppercase()) {
+            let name = word.strip_suffix(':').unwrap_or(word).to_string();
+            if !is_valid_label(&name) {
+                return Err(AsmError::new(line, format!("invalid label '{}'", name)));
+            }
+            label = Some(name);
+            tokens.next();
+        }
+    }
+
+    let mnemonic = match tokens.next() {
+        Some(Token::Word(word)) => {
+            let upper = word.to_uppercase();
+            if !is_mnemonic(&upper) {
+                return Err(AsmError::new(line, format!("unknown mnemonic '{}'", word)));
+            }
+            Some(upper)
+        }
+        Some(Token::Str(_)) => return Err(AsmError::new(line, "unexpected string")),
+        None => None,
+    };
+
+    if label.is_none() && mnemonic.is_none() {
+        return Ok(None);
+    }
+
+    Ok(Some(Statement {
+        line,
+        label,
+        mnemonic,
+        operands: tokens.collect(),
+    }))
+}
+
+fn is_valid_label(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn branch_flags(mnemonic: &str) -> Option<u16> {
+    let flags = mnemonic.strip_prefix("BR")?;
+    if flags.is_empty() {
+        return Some(0x7);
+    }
+
+    let mut nzp = 0;
+    let mut last = 0;
+    for c in flags.chars() {
+        let (bit, order) = match c {
+            'N' => (0x4, 1),
+            'Z' => (0x2, 2),
+            'P' => (0x1, 3),
+            _ => return None,
+        };
+        if order <= last {
+            return None;
+        }
+        last = order;
+        nzp |= bit;
+    }
+    Some(nzp)
+}
+
+fn is_mnemonic(word: &str) -> bool {
+    matches!(
+        word,
+        "ADD"
+            | "AND"
+            | "NOT"
+            | "JMP"
+            | "RET"
+            | "JSR"
+            | "JSRR"
+            | "LD"
+            | "LDI"
+            | "LDR"
+            | "LEA"
+            | "ST"
+            | "STI"
+            | "STR"
+            | "TRAP"
+            | "RTI"
+            | "NOP"
+            | "GETC"
+            | "OUT"
+            | "PUTS"
+            | "IN"
+            | "PUTSP"
+            | "HALT"
+            | ".ORIG"
+            | ".FILL"
+            | ".BLKW"
+            | ".STRINGZ"
+            | ".END"
+    ) || branch_flags(word).is_some()
+}
+
+fn statement_size(statement: &Statement) -> Result<usize, AsmError> {
+    match statement.mnemonic.as_deref() {
+        None => Ok(0),
+        Some(".BLKW") => {
+            expect_operands(statement, 1)?;
+            let count = number_operand(statement, 0)?;
+            if !(1..MEMORY_MAX as i32).contains(&count) {
+                return Err(AsmError::new(statement.line, ".BLKW count out of range"));
+            }
+            Ok(count as usize)
+        }
+        Some(".STRINGZ") => {
+            expect_operands(statement, 1)?;
+            match &statement.operands[0] {
+                // each character takes one word, so it must fit in one
+                Token::Str(s) => match s.chars().find(|c| !c.is_ascii()) {
+                    Some(c) => Err(AsmError::new(
+                        statement.line,
+                        format!("non-ASCII character '{}' in string", c),
+                    )),
+                    None => Ok(s.len() + 1),
+                },
+                Token::Word(_) => Err(AsmError::new(statement.line, "expected a string")),
+            }
+        }
+        Some(_) => Ok(1),
+    }
+}
+
+fn expect_operands(statement: &Statement, count: usize) -> Result<(), AsmError> {
+    if statement.operands.len() != count {
+        return Err(AsmError::new(
+            statement.line,
+            format!(
+                "{} expects {} operand(s), found {}",
+                statement.mnemonic.as_deref().unwrap_or(""),
+                count,
+                statement.operands.len()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+fn word_operand(statement: &Statement, index: usize) -> Result<&str, AsmError> {
+    match &statement.operands[index] {
+        Token::Word(word) => Ok(word),
+        Token::Str(_) => Err(AsmError::new(statement.line, "unexpected string")),
+    }
+}
+
+/// Parses `#10`, `#-3`, `x3000`, `0x3000`, `b101` or a bare decimal literal.
+pub fn parse_number(text: &str) -> Option<i32> {
+    let (negative, body) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text),
+    };
+
+    let value = if let Some(dec) = body.strip_prefix('#') {
+        return dec
+            .parse::<i32>()
+            .ok()
+            .map(|v| if negative { -v } else { v });
+    } else if let Some(hex) = body
+        .strip_prefix("0x")
+        .or_else(|| body.strip_prefix("0X"))
+        .or_else(|| body.strip_prefix('x'))
+        .or_else(|| body.strip_prefix('X'))
+    {
+        if let Some(hex) = hex.strip_prefix('-') {
+            -i32::from_str_radix(hex, 16).ok()?
+        } else {
+            i32::from_str_radix(hex, 16).ok()?
+        }
+    } else if let Some(bin) = body.strip_prefix('b').or_else(|| body.strip_prefix('B')) {
+        i32::from_str_radix(bin, 2).ok()?
+    } else if body.starts_with(|c: char| c.is_ascii_digit()) {
+        body.parse::<i32>().ok()?
+    } else {
+        return None;
+    };
+
+    Some(if negative { -value } else { value })
+}
+
+fn number_operand(statement: &Statement, index: usize) -> Result<i32, AsmError> {
+    let word = word_operand(statement, index)?;
+    parse_number(word).ok_or_else(|| {
+        AsmError::new(
+            statement.line,
+            format!("expected a number, found '{}'", word),
+        )
+    })
+}
+
+fn parse_register(word: &str) -> Option<u16> {
+    let bytes = word.as_bytes();
+    if bytes.len() == 2
+        && (bytes[0] == b'R' || bytes[0] == b'r')
+        && (b'0'..=b'7').contains(&bytes[1])
+    {
+        return Some((bytes[1] - b'0') as u16);
+    }
+    None
+}
+
+fn register_operand(statement: &Statement, index: usize) -> Result<u16, AsmError> {
+    let word = word_operand(statement, index)?;
+    parse_register(word).ok_or_else(|| {
+        AsmError::new(
+            statement.line,
+            format!("expected a register, found '{}'", word),
+        )
+    })
+}
+
+fn check_signed(statement: &Statement, value: i32, bits: u32) -> Result<u16, AsmError> {
+    let min = -(1 << (bits - 1));
+    let max = (1 << (bits - 1)) - 1;
+    if value < min || value > max {
+        return Err(AsmError::new(
+            statement.line,
+            format!("value {} does not fit in {} bits", value, bits),
+        ));
+    }
+    Ok((value as u16) & ((1 << bits) - 1))
+}
+
+fn immediate_operand(statement: &Statement, index: usize, bits: u32) -> Result<u16, AsmError> {
+    let value = number_operand(statement, index)?;
+    check_signed(statement, value, bits)
+}
+
+fn offset_operand(
+    statement: &Statement,
+    index: usize,
+    bits: u32,
+    pc: u16,
+    symbols: &SymbolTable,
+) -> Result<u16, AsmError> {
+    let word = word_operand(statement, index)?;
+    let offset = match parse_number(word) {
+        Some(value) => value,
+        None => {
+            let target = symbols.address_of(word).ok_or_else(|| {
+                AsmError::new(statement.line, format!("undefined label '{}'", word))
+            })?;
+            target as i32 - (pc as i32 + 1)
+        }
+    };
+    check_signed(statement, offset, bits)
+}
+
+fn encode_statement(
+    statement: &Statement,
+    pc: u16,
+    symbols: &SymbolTable,
+    words: &mut Vec<u16>,
+) -> Result<(), AsmError> {
+    let mnemonic = match statement.mnemonic.as_deref() {
+        Some(mnemonic) => mnemonic,
+        None => return Ok(()),
+    };
+
+    let op = |opcode: Opcode| (opcode as u16) << 12;
+    let trap = |trap: Trap| op(Opcode::OpTrap) | trap as u16;
+
+    let word = match mnemonic {
+        ".FILL" => {
+            expect_operands(statement, 1)?;
+            let operand = word_operand(statement, 0)?;
+            match parse_number(operand) {
+                Some(value) if (-0x8000..=0xFFFF).contains(&value) => value as u16,
+                Some(_) => return Err(AsmError::new(statement.line, ".FILL value out of range")),
+                None => symbols.address_of(operand).ok_or_else(|| {
+                    AsmError::new(statement.line, format!("undefined label '{}'", operand))
+                })?,
+            }
+        }
+        ".BLKW" => {
+            let count = number_operand(statement, 0)?;
+            words.extend(std::iter::repeat_n(0, count as usize));
+            return Ok(());
+        }
+        ".STRINGZ" => {
+            if let Token::Str(s) = &statement.operands[0] {
+                words.extend(s.bytes().map(u16::from));
+                words.push(0);
+            }
+            return Ok(());
+        }
+        "ADD" | "AND" => {
+            expect_operands(statement, 3)?;
+            let opcode = if mnemonic == "ADD" {
+                Opcode::OpAdd
+            } else {
+                Opcode::OpAnd
+            };
+            let dr = register_operand(statement, 0)?;
+            let sr1 = register_operand(statement, 1)?;
+            let src2 = if parse_register(word_operand(statement, 2)?).is_some() {
+                register_operand(statement, 2)?
+            } else {
+                0x20 | immediate_operand(statement, 2, 5)?
+            };
+            op(opcode) | dr << 9 | sr1 << 6 | src2
+        }
+        "NOT" => {
+            expect_operands(statement, 2)?;
+            let dr = register_operand(statement, 0)?;
+            let sr = register_operand(statement, 1)?;
+            op(Opcode::OpNot) | dr << 9 | sr << 6 | 0x3F
+        }
+        "JMP" => {
+            expect_operands(statement, 1)?;
+            op(Opcode::OpJmp) | register_operand(statement, 0)? << 6
+        }
+        "RET" => {
+            expect_operands(statement, 0)?;
+            op(Opcode::OpJmp) | 7 << 6
+        }
+        "JSR" => {
+            expect_operands(statement, 1)?;
+            op(Opcode::OpJsr) | 1 << 11 | offset_operand(statement, 0, 11, pc, symbols)?
+        }
+        "JSRR" => {
+            expect_operands(statement, 1)?;
+            op(Opcode::OpJsr) | register_operand(statement, 0)? << 6
+        }
+        "LD" | "LDI" | "LEA" | "ST" | "STI" => {
+            expect_operands(statement, 2)?;
+            let opcode = match mnemonic {
+                "LD" => Opcode::OpLd,
+                "LDI" => Opcode::OpLdi,
+                "LEA" => Opcode::OpLea,
+                "ST" => Opcode::OpSt,
+                _ => Opcode::OpSti,
+            };
+            let r = register_operand(statement, 0)?;
+            op(opcode) | r << 9 | offset_operand(statement, 1, 9, pc, symbols)?
+        }
+        "LDR" | "STR" => {
+            expect_operands(statement, 3)?;
+            let opcode = if mnemonic == "LDR" {
+                Opcode::OpLdr
+            } else {
+                Opcode::OpStr
+            };
+            let r = register_operand(statement, 0)?;
+            let base = register_operand(statement, 1)?;
+            op(opcode) | r << 9 | base << 6 | immediate_operand(statement, 2, 6)?
+        }
+        "TRAP" => {
+            expect_operands(statement, 1)?;
+            let vector = number_operand(statement, 0)?;
+            if !(0..=0xFF).contains(&vector) {
+                return Err(AsmError::new(statement.line, "trap vector out of range"));
+            }
+            op(Opcode::OpTrap) | vector as u16
+        }
+        "RTI" => {
+            expect_operands(statement, 0)?;
+            op(Opcode::OpRti)
+        }
+        // a branch on no condition, as the disassembler lists x0000
+        "NOP" => {
+            expect_operands(statement, 0)?;
+            op(Opcode::OpBr)
+        }
+        "GETC" | "OUT" | "PUTS" | "IN" | "PUTSP" | "HALT" => {
+            expect_operands(statement, 0)?;
+            trap(match mnemonic {
+                "GETC" => Trap::TrapGetc,
+                "OUT" => Trap::TrapOut,
+                "PUTS" => Trap::TrapPuts,
+                "IN" => Trap::TrapIn,
+                "PUTSP" => Trap::TrapPutsp,
+                _ => Trap::TrapHalt,
+            })
+        }
+        _ => {
+            let nzp = branch_flags(mnemonic).ok_or_else(|| {
+                AsmError::new(statement.line, format!("unknown mnemonic '{}'", mnemonic))
+            })?;
+            expect_operands(statement, 1)?;
+            op(Opcode::OpBr) | nzp << 9 | offset_operand(statement, 0, 9, pc, symbols)?
+        }
+    };
+
+    words.push(word);
+    Ok(())
+}
@@ -0,0 +1,374 @@
+use crate::decoder::{decode, Instruction, Operand};
+use crate::emulator::{Emulator, Mmu, DEVICE_PAGE, KBSR_INTERRUPT_ENABLE, MEMORY_MAX};
+use crate::error::EmulatorError;
+use crate::instruction::{update_flags, wrapping_add};
+use crate::register::{MemoryMappedRegister, Register};
+use crate::trace::Tracer;
+
+use std::sync::Arc;
+
+/// Longest run of instructions translated into one block.
+pub const MAX_BLOCK_LEN: usize = 64;
+
+/// One translated instruction.
+///
+/// PC-relative operands are resolved to absolute addresses when the block is
+/// translated, so executing a block never looks at the PC until it exits.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MicroOp {
+    AddReg {
+        dr: Register,
+        sr1: Register,
+        sr2: Register,
+    },
+    AddImm {
+        dr: Register,
+        sr1: Register,
+        imm: u16,
+    },
+    AndReg {
+        dr: Register,
+        sr1: Register,
+        sr2: Register,
+    },
+    AndImm {
+        dr: Register,
+        sr1: Register,
+        imm: u16,
+    },
+    Not {
+        dr: Register,
+        sr: Register,
+    },
+    /// LEA: load a constant address and set the condition codes.
+    Const {
+        dr: Register,
+        value: u16,
+    },
+    Load {
+        dr: Register,
+        address: u16,
+    },
+    LoadIndirect {
+        dr: Register,
+        pointer: u16,
+    },
+    LoadBase {
+        dr: Register,
+        base: Register,
+        offset: u16,
+    },
+    Store {
+        sr: Register,
+        address: u16,
+    },
+    StoreIndirect {
+        sr: Register,
+        pointer: u16,
+    },
+    StoreBase {
+        sr: Register,
+        base: Register,
+        offset: u16,
+    },
+    Branch {
+        nzp: u16,
+        target: u16,
+    },
+    Jump {
+        base: Register,
+    },
+    Call {
+        target: u16,
+    },
+    CallRegister {
+        base: Register,
+    },
+    /// Handed to the interpreter: TRAP, RTI, the reserved opcode, and
+    /// accesses to the device page known at translation time.
+    Interpret,
+}
+
+impl MicroOp {
+    /// Translates the instruction at `address`.
+    pub fn translate(address: u16, instruction: Instruction) -> MicroOp {
+        let next = address.wrapping_add(1);
+        let relative = |offset: i16| wrapping_add(next, offset as u16);
+        let device = |address: u16| address >= DEVICE_PAGE;
+
+        match instruction {
+            Instruction::Add { dr, sr1, src2 } => match src2 {
+                Operand::Register(sr2) => MicroOp::AddReg { dr, sr1, sr2 },
+                Operand::Immediate(imm) => MicroOp::AddImm {
+                    dr,
+                    sr1,
+                    imm: imm as u16,
+                },
+            },
+            Instruction::And { dr, sr1, src2 } => match src2 {
+                Operand::Register(sr2) => MicroOp::AndReg { dr, sr1, sr2 },
+                Operand::Immediate(imm) => MicroOp::AndImm {
+                    dr,
+                    sr1,
+                    imm: imm as u16,
+                },
+            },
+            Instruction::Not { dr, sr } => MicroOp::Not { dr, sr },
+            Instruction::Lea { dr, offset } => MicroOp::Const {
+                dr,
+                value: relative(offset),
+            },
+            Instruction::Ld { dr, offset } if !device(relative(offset)) => MicroOp::Load {
+                dr,
+                address: relative(offset),
+            },
+            Instruction::Ldi { dr, offset } if !device(relative(offset)) => MicroOp::LoadIndirect {
+                dr,
+                pointer: relative(offset),
+            },
+            Instruction::Ldr { dr, base, offset } => MicroOp::LoadBase {
+                dr,
+                base,
+                offset: offset as u16,
+            },
+            Instruction::St { sr, offset } if !device(relative(offset)) => MicroOp::Store {
+                sr,
+                address: relative(offset),
+            },
+            Instruction::Sti { sr, offset } if !device(relative(offset)) => {
+                MicroOp::StoreIndirect {
+                    sr,
+                    pointer: relative(offset),
+                }
+            }
+            Instruction::Str { sr, base, offset } => MicroOp::StoreBase {
+                sr,
+                base,
+                offset: offset as u16,
+            },
+            Instruction::Br { n, z, p, offset } => MicroOp::Branch {
+                nzp: (n as u16) << 2 | (z as u16) << 1 | p as u16,
+                target: relative(offset),
+            },
+            Instruction::Jmp { base } => MicroOp::Jump { base },
+            Instruction::Jsr { offset } => MicroOp::Call {
+                target: relative(offset),
+            },
+            Instruction::Jsrr { base } => MicroOp::CallRegister { base },
+            _ => MicroOp::Interpret,
+        }
+    }
+
+    /// Returns `true` if the block must end after this instruction.
+    pub fn ends_block(self) -> bool {
+        matches!(
+            self,
+            MicroOp::Branch { .. }
+                | MicroOp::Jump { .. }
+                | MicroOp::Call { .. }
+                | MicroOp::CallRegister { .. }
+                | MicroOp::Interpret
+        )
+    }
+}
+
+/// Translated blocks, keyed by the address of their first instruction.
+///
+/// Every address a block covers is marked in the [`Mmu`]; a write to a
+/// marked address is reported back through [`Mmu::take_stale`] and drops
+/// every block covering it before the next block runs.
+#[derive(Default)]
+pub struct BlockCache {
+    blocks: Vec<Option<Arc<[MicroOp]>>>,
+}
+
+impl BlockCache {
+    pub fn new() -> Self {
+        BlockCache { blocks: Vec::new() }
+    }
+
+    /// Drops every block covering an address written since the last call.
+    pub fn invalidate_stale(&mut self, memory: &mut Mmu) {
+        if self.blocks.is_empty() {
+            return;
+        }
+        for address in memory.take_stale() {
+            let first = (address as usize).saturating_sub(MAX_BLOCK_LEN - 1);
+            for start in first..=address as usize {
+                let covers = self.blocks[start]
+                    .as_ref()
+                    .is_some_and(|block| start + block.len() > address as usize);
+                if covers {
+                    self.blocks[start] = None;
+                }
+            }
+        }
+    }
+
+    /// Returns the block starting at `start`, translating it first if it is
+    /// not cached. `start` must be below the device page.
+    pub fn get(&mut self, start: u16, memory: &mut Mmu) -> Arc<[MicroOp]> {
+        if self.blocks.is_empty() {
+            self.blocks = vec![None; MEMORY_MAX];
+        }
+        if let Some(block) = &self.blocks[start as usize] {
+            return block.clone();
+        }
+
+        let mut ops = Vec::new();
+        let mut address = start;
+        while address < DEVICE_PAGE && ops.len() < MAX_BLOCK_LEN {
+            let op = MicroOp::translate(address, decode(memory.peek(address as usize)));
+            memory.mark_translated(address);
+            ops.push(op);
+            if op.ends_block() {
+                break;
+            }
+            address += 1;
+        }
+
+        let block: Arc<[MicroOp]> = ops.into();
+        self.blocks[start as usize] = Some(block.clone());
+        block
+    }
+}
+
+/// Runs the block at the PC, executing at most `budget` instructions, and
+/// returns how many were executed.
+///
+/// The interpreter takes over for a single [`step`](Emulator::step) when
+/// the next instruction touches the device page, while tracing, and while
+/// keyboard interrupts are enabled, since those need checks between every
+/// instruction.
+pub fn execute(emu: &mut Emulator, budget: u64) -> Result<u64, EmulatorError> {
+    let mut pc = emu.registers.get_value(Register::Rpc);
+    let kbsr = emu.memory.peek(MemoryMappedRegister::Kbsr as usize);
+    if pc >= DEVICE_PAGE
+        || kbsr & KBSR_INTERRUPT_ENABLE != 0
+        || emu.tracer.as_ref().is_some_and(Tracer::is_active)
+    {
+        emu.step()?;
+        return Ok(1);
+    }
+
+    emu.blocks.invalidate_stale(&mut emu.memory);
+    let block = emu.blocks.get(pc, &mut emu.memory);
+
+    let mut executed = 0;
+    for &op in block.iter() {
+        if executed == budget {
+            break;
+        }
+        let next = pc.wrapping_add(1);
+        let regs = &emu.registers;
+        match op {
+            MicroOp::AddReg { dr, sr1, sr2 } => {
+                let value = wrapping_add(regs.get_value(sr1), regs.get_value(sr2));
+                set(emu, dr, value);
+            }
+            MicroOp::AddImm { dr, sr1, imm } => {
+                let value = wrapping_add(regs.get_value(sr1), imm);
+                set(emu, dr, value);
+            }
+            MicroOp::AndReg { dr, sr1, sr2 } => {
+                let value = regs.get_value(sr1) & regs.get_value(sr2);
+                set(emu, dr, value);
+            }
+            MicroOp::AndImm { dr, sr1, imm } => {
+                let value = regs.get_value(sr1) & imm;
+                set(emu, dr, value);
+            }
+            MicroOp::Not { dr, sr } => {
+                let value = !regs.get_value(sr);
+                set(emu, dr, value);
+            }
+            MicroOp::Const { dr, value } => set(emu, dr, value),
+            MicroOp::Load { dr, address } => {
+                let value = emu.memory.peek(address as usize);
+                set(emu, dr, value);
+            }
+            MicroOp::LoadIndirect { dr, pointer } => {
+                let address = emu.memory.peek(pointer as usize);
+                if address >= DEVICE_PAGE {
+                    return interpret(emu, pc, executed);
+                }
+                let value = emu.memory.peek(address as usize);
+                set(emu, dr, value);
+            }
+            MicroOp::LoadBase { dr, base, offset } => {
+                let address = wrapping_add(regs.get_value(base), offset);
+                if address >= DEVICE_PAGE {
+                    return interpret(emu, pc, executed);
+                }
+                let value = emu.memory.peek(address as usize);
+                set(emu, dr, value);
+            }
+            MicroOp::Store { sr, address } => {
+                emu.memory.write(address as usize, regs.get_value(sr));
+            }
+            MicroOp::StoreIndirect { sr, pointer } => {
+                let address = emu.memory.peek(pointer as usize);
+                if address >= DEVICE_PAGE {
+                    return interpret(emu, pc, executed);
+                }
+                emu.memory.write(address as usize, regs.get_value(sr));
+            }
+            MicroOp::StoreBase { sr, base, offset } => {
+                let address = wrapping_add(regs.get_value(base), offset);
+                if address >= DEVICE_PAGE {
+                    return interpret(emu, pc, executed);
+                }
+                emu.memory.write(address as usize, regs.get_value(sr));
+            }
+            MicroOp::Branch { nzp, target } => {
+                pc = if nzp & regs.get_value(Register::Rcond) != 0 {
+                    target
+                } else {
+                    next
+                };
+                executed += 1;
+                break;
+            }
+            MicroOp::Jump { base } => {
+                pc = regs.get_value(base);
+                executed += 1;
+                break;
+            }
+            MicroOp::Call { target } => {
+                emu.registers.update(Register::Rr7, next);
+                pc = target;
+                executed += 1;
+                break;
+            }
+            MicroOp::CallRegister { base } => {
+                pc = regs.get_value(base);
+                emu.registers.update(Register::Rr7, next);
+                executed += 1;
+                break;
+            }
+            MicroOp::Interpret => return interpret(emu, pc, executed),
+        }
+
+        pc = next;
+        executed += 1;
+        // a store into translated code ends the block, which may be stale
+        if emu.memory.has_stale() {
+            break;
+        }
+    }
+
+    emu.registers.update(Register::Rpc, pc);
+    Ok(executed)
+}
+
+fn set(emu: &mut Emulator, dr: Register, value: u16) {
+    emu.registers.update(dr, value);
+    update_flags(emu, dr);
+}
+
+/// Lets the interpreter execute the instruction at `pc`.
+fn interpret(emu: &mut Emulator, pc: u16, executed: u64) -> Result<u64, EmulatorError> {
+    emu.registers.update(Register::Rpc, pc);
+    emu.step()?;
+    Ok(executed + 1)
+}
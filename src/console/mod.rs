@@ -0,0 +1,152 @@
+use crate::keyboard::Keyboard;
+
+use std::collections::VecDeque;
+use std::io::{self, stdin, stdout, Read, Stdout, Write};
+use std::sync::{Arc, Mutex};
+
+/// Character I/O between the emulated machine and the outside world.
+///
+/// The keyboard and display device registers and the native trap routines
+/// all go through the console owned by the [`Emulator`](crate::Emulator),
+/// so programs can run against a terminal, files, pipes or in-memory
+/// buffers alike.
+pub trait Console: Send {
+    /// Returns the next input byte if one is available, without blocking.
+    fn poll(&mut self) -> Option<u8>;
+
+    /// Waits for the next input byte. Returns `None` at end of input.
+    fn read(&mut self) -> Option<u8>;
+
+    /// Returns `true` once the input has ended and every byte was read.
+    fn is_closed(&self) -> bool {
+        false
+    }
+
+    /// Writes one byte of output.
+    fn write(&mut self, byte: u8) -> io::Result<()>;
+
+    /// Flushes buffered output.
+    fn flush(&mut self) -> io::Result<()>;
+}
+
+/// A console reading from any byte stream and writing to any writer.
+///
+/// Input is read on a background thread so [`poll`](Console::poll) never
+/// blocks. This covers files, pipes and the process's own terminal.
+pub struct StreamConsole<W: Write + Send> {
+    input: Keyboard,
+    output: W,
+}
+
+impl<W: Write + Send> StreamConsole<W> {
+    pub fn new<R: Read + Send + 'static>(input: R, output: W) -> Self {
+        StreamConsole {
+            input: Keyboard::from_reader(input),
+            output,
+        }
+    }
+}
+
+/// The console attached to the process's standard input and output.
+pub fn terminal() -> StreamConsole<Stdout> {
+    StreamConsole::new(stdin(), stdout())
+}
+
+impl<W: Write + Send> Console for StreamConsole<W> {
+    fn poll(&mut self) -> Option<u8> {
+        self.input.poll()
+    }
+
+    fn read(&mut self) -> Option<u8> {
+        self.input.read()
+    }
+
+    fn is_closed(&self) -> bool {
+        self.input.is_closed()
+    }
+
+    fn write(&mut self, byte: u8) -> io::Result<()> {
+        self.output.write_all(&[byte])
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.output.flush()
+    }
+}
+
+/// A cloneable handle to a byte buffer, used to capture console output.
+#[derive(Debug, Clone, Default)]
+pub struct SharedBuffer {
+    bytes: Arc<Mutex<Vec<u8>>>,
+}
+
+impl SharedBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a copy of everything written so far.
+    pub fn contents(&self) -> Vec<u8> {
+        self.bytes.lock().unwrap().clone()
+    }
+
+    /// Returns everything written so far, lossily decoded as UTF-8.
+    pub fn to_string_lossy(&self) -> String {
+        String::from_utf8_lossy(&self.contents()).into_owned()
+    }
+}
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.bytes.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// An in-memory console with scripted input and captured output.
+#[derive(Debug, Clone, Default)]
+pub struct BufferConsole {
+    input: VecDeque<u8>,
+    output: SharedBuffer,
+}
+
+impl BufferConsole {
+    pub fn new(input: &[u8]) -> Self {
+        BufferConsole {
+            input: input.iter().copied().collect(),
+            output: SharedBuffer::new(),
+        }
+    }
+
+    /// Returns a handle to the captured output that stays valid after the
+    /// console has been handed to an emulator.
+    pub fn output(&self) -> SharedBuffer {
+        self.output.clone()
+    }
+}
+
+impl Console for BufferConsole {
+    fn poll(&mut self) -> Option<u8> {
+        self.input.pop_front()
+    }
+
+    fn read(&mut self) -> Option<u8> {
+        self.input.pop_front()
+    }
+
+    fn is_closed(&self) -> bool {
+        self.input.is_empty()
+    }
+
+    fn write(&mut self, byte: u8) -> io::Result<()> {
+        self.output.write_all(&[byte])
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,419 @@
+use crate::assembler::parse_number;
+use crate::decoder::{decode, Instruction};
+use crate::disassembler::{disassemble, disassemble_memory};
+use crate::emulator::Emulator;
+use crate::error::EmulatorError;
+use crate::register::Register;
+use crate::symbol::SymbolTable;
+
+use std::collections::BTreeSet;
+use std::io::{BufRead, Write};
+
+/// Why execution returned control to the debugger prompt.
+#[derive(Debug)]
+pub enum StopReason {
+    Step,
+    Breakpoint(u16),
+    Finished,
+    Halted,
+    Fault(EmulatorError),
+}
+
+/// Interactive command-line debugger driving an [`Emulator`].
+///
+/// Commands are read line by line from `input` and all debugger output is
+/// written to `output`, so the REPL can be scripted.
+pub struct Debugger<R: BufRead, W: Write> {
+    input: R,
+    output: W,
+    breakpoints: BTreeSet<u16>,
+    symbols: SymbolTable,
+}
+
+const HELP: &str = "Commands:
+    s, step [n]             Execute one (or n) instructions.
+    n, next                 Step over JSR, JSRR and TRAP.
+    c, continue             Run until a breakpoint or HALT.
+    finish                  Run until the current subroutine returns.
+    b, break <addr|label>   Set a breakpoint; labels may take an offset,
+                            as in LOOP+3.
+    d, delete <addr|label>  Remove a breakpoint.
+    breakpoints             List breakpoints.
+    r, regs                 Dump the registers.
+    x, mem <addr> [n]       Dump n words of memory.
+    l, list [addr] [n]      Disassemble n instructions.
+    set <reg|addr> <value>  Edit a register (R0-R7, PC, COND, PSR, SSP,
+                            USP) or memory.
+    h, help                 Show this message.
+    q, quit                 Leave the debugger.";
+
+impl<R: BufRead, W: Write> Debugger<R, W> {
+    pub fn new(input: R, output: W) -> Self {
+        Debugger {
+            input,
+            output,
+            breakpoints: BTreeSet::new(),
+            symbols: SymbolTable::new(),
+        }
+    }
+
+    /// Adds `symbols` to the emulator's [`symbols`](Emulator::symbols) when
+    /// the session starts, for resolving labels in commands and listings.
+    pub fn with_symbols(mut self, symbols: SymbolTable) -> Self {
+        self.symbols = symbols;
+        self
+    }
+
+    pub fn add_breakpoint(&mut self, address: u16) {
+        self.breakpoints.insert(address);
+    }
+
+    pub fn remove_breakpoint(&mut self, address: u16) -> bool {
+        self.breakpoints.remove(&address)
+    }
+
+    /// Runs the REPL until the user quits or input is exhausted.
+    pub fn run(&mut self, emu: &mut Emulator) -> std::io::Result<()> {
+        emu.symbols.extend(&self.symbols);
+        self.show_location(emu)?;
+
+        loop {
+            write!(
+                self.output,
+                "(lc3db {}) ",
+                emu.describe_address(emu.register(Register::Rpc))
+            )?;
+            self.output.flush()?;
+
+            let mut line = String::new();
+            if self.input.read_line(&mut line)? == 0 {
+                writeln!(self.output)?;
+                return Ok(());
+            }
+
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let (command, args) = match words.split_first() {
+                Some((command, args)) => (*command, args),
+                None => continue,
+            };
+
+            match command {
+                "q" | "quit" => return Ok(()),
+                "h" | "help" => writeln!(self.output, "{}", HELP)?,
+                "s" | "step" => {
+                    let count = match args.first() {
+                        Some(arg) => match parse_number(arg) {
+                            Some(n) if n > 0 => n as usize,
+                            _ => {
+                                writeln!(self.output, "Invalid step count '{}'", arg)?;
+                                continue;
+                            }
+                        },
+                        None => 1,
+                    };
+                    let reason = self.step(emu, count);
+                    self.report(emu, reason)?;
+                }
+                "n" | "next" => {
+                    let reason = self.next(emu);
+                    self.report(emu, reason)?;
+                }
+                "c" | "continue" => {
+                    let reason = self.resume(emu, None);
+                    self.report(emu, reason)?;
+                }
+                "finish" => {
+                    let reason = self.finish(emu);
+                    self.report(emu, reason)?;
+                }
+                "b" | "break" => match args.first().and_then(|arg| self.resolve(emu, arg)) {
+                    Some(address) => {
+                        self.add_breakpoint(address);
+                        writeln!(self.output, "Breakpoint at x{:04X}", address)?;
+                    }
+                    None => writeln!(self.output, "Usage: break <addr|label>")?,
+                },
+                "d" | "delete" => match args.first().and_then(|arg| self.resolve(emu, arg)) {
+                    Some(address) => {
+                        if self.remove_breakpoint(address) {
+                            writeln!(self.output, "Deleted breakpoint at x{:04X}", address)?;
+                        } else {
+                            writeln!(self.output, "No breakpoint at x{:04X}", address)?;
+                        }
+                    }
+                    None => writeln!(self.output, "Usage: delete <addr|label>")?,
+                },
+                "breakpoints" => {
+                    if self.breakpoints.is_empty() {
+                        writeln!(self.output, "No breakpoints")?;
+                    }
+                    for address in &self.breakpoints {
+                        writeln!(self.output, "  x{:04X}", address)?;
+                    }
+                }
+                "r" | "regs" => self.dump_registers(emu)?,
+                "x" | "mem" => {
+                    let start = args.first().and_then(|arg| self.resolve(emu, arg));
+                    let count = args.get(1).and_then(|arg| parse_number(arg)).unwrap_or(8);
+                    match start {
+                        Some(start) => self.dump_memory(emu, start, count.max(1) as usize)?,
+                        None => writeln!(self.output, "Usage: mem <addr> [count]")?,
+                    }
+                }
+                "l" | "list" => {
+                    let start = match args.first() {
+                        Some(arg) => self.resolve(emu, arg),
+                        None => Some(emu.register(Register::Rpc)),
+                    };
+                    let count = args.get(1).and_then(|arg| parse_number(arg)).unwrap_or(8);
+                    match start {
+                        Some(start) => {
+                            for line in disassemble_memory(
+                                &emu.memory,
+                                start,
+                                count.max(1) as usize,
+                                Some(&emu.symbols),
+                            ) {
+                                writeln!(self.output, "{}", line)?;
+                            }
+                        }
+                        None => writeln!(self.output, "Usage: list [addr] [count]")?,
+                    }
+                }
+                "set" => match args {
+                    [target, value] => self.set(emu, target, value)?,
+                    _ => writeln!(self.output, "Usage: set <reg|addr> <value>")?,
+                },
+                _ => writeln!(self.output, "Unknown command '{}'. Try 'help'.", command)?,
+            }
+        }
+    }
+
+    /// Executes up to `count` instructions, stopping early on HALT or a
+    /// breakpoint.
+    pub fn step(&mut self, emu: &mut Emulator, count: usize) -> StopReason {
+        for i in 0..count {
+            if !emu.running {
+                return StopReason::Halted;
+            }
+            let pc = emu.register(Register::Rpc);
+            if i > 0 && self.breakpoints.contains(&pc) {
+                return StopReason::Breakpoint(pc);
+            }
+            if let Err(err) = emu.step() {
+                return StopReason::Fault(err);
+            }
+        }
+        if emu.running {
+            StopReason::Step
+        } else {
+            StopReason::Halted
+        }
+    }
+
+    /// Steps over subroutine calls and traps, otherwise behaves like `step`.
+    pub fn next(&mut self, emu: &mut Emulator) -> StopReason {
+        let pc = emu.register(Register::Rpc);
+        let instr = emu.memory.peek(pc as usize);
+        match decode(instr) {
+            Instruction::Jsr { .. } | Instruction::Jsrr { .. } | Instruction::Trap { .. } => {
+                self.resume(emu, Some(pc.wrapping_add(1)))
+            }
+            _ => self.step(emu, 1),
+        }
+    }
+
+    /// Runs until a breakpoint, HALT, or `until` is reached.
+    pub fn resume(&mut self, emu: &mut Emulator, until: Option<u16>) -> StopReason {
+        let mut first = true;
+        while emu.running {
+            let pc = emu.register(Register::Rpc);
+            if !first {
+                if Some(pc) == until {
+                    return StopReason::Step;
+                }
+                if self.breakpoints.contains(&pc) {
+                    return StopReason::Breakpoint(pc);
+                }
+            }
+            first = false;
+            if let Err(err) = emu.step() {
+                return StopReason::Fault(err);
+            }
+        }
+        StopReason::Halted
+    }
+
+    /// Runs until the `RET` that leaves the current subroutine executes.
+    pub fn finish(&mut self, emu: &mut Emulator) -> StopReason {
+        let mut depth: usize = 0;
+        let mut first = true;
+
+        while emu.running {
+            let pc = emu.register(Register::Rpc);
+            if !first && self.breakpoints.contains(&pc) {
+                return StopReason::Breakpoint(pc);
+            }
+            first = false;
+
+            let instr = emu.memory.peek(pc as usize);
+            if let Err(err) = emu.step() {
+                return StopReason::Fault(err);
+            }
+
+            match decode(instr) {
+                Instruction::Jsr { .. } | Instruction::Jsrr { .. } => depth += 1,
+                Instruction::Trap { .. } if emu.register(Register::Rpc) != pc.wrapping_add(1) => {
+                    depth += 1
+                }
+                Instruction::Jmp {
+                    base: Register::Rr7,
+                } => {
+                    if depth == 0 {
+                        return StopReason::Finished;
+                    }
+                    depth -= 1;
+                }
+                _ => {}
+            }
+        }
+        StopReason::Halted
+    }
+
+    fn resolve(&self, emu: &Emulator, text: &str) -> Option<u16> {
+        match parse_number(text) {
+            Some(value) if (0..=0xFFFF).contains(&value) => Some(value as u16),
+            Some(_) => None,
+            None => match text.split_once('+') {
+                Some((label, offset)) => {
+                    let offset = parse_number(offset).filter(|n| (0..=0xFFFF).contains(n))?;
+                    Some(emu.symbols.address_of(label)?.wrapping_add(offset as u16))
+                }
+                None => emu.symbols.address_of(text),
+            },
+        }
+    }
+
+    fn report(&mut self, emu: &Emulator, reason: StopReason) -> std::io::Result<()> {
+        match reason {
+            StopReason::Breakpoint(address) => writeln!(
+                self.output,
+                "Breakpoint hit at {}",
+                emu.describe_address(address)
+            )?,
+            StopReason::Finished => writeln!(self.output, "Returned from subroutine")?,
+            StopReason::Halted => {
+                writeln!(self.output, "Program halted")?;
+                return Ok(());
+            }
+            StopReason::Fault(err) => {
+                writeln!(self.output, "Program stopped: {}", err)?;
+                return Ok(());
+            }
+            StopReason::Step => {}
+        }
+        self.show_location(emu)
+    }
+
+    fn show_location(&mut self, emu: &Emulator) -> std::io::Result<()> {
+        let pc = emu.register(Register::Rpc);
+        let instr = emu.memory.peek(pc as usize);
+        writeln!(
+            self.output,
+            "x{:04X}: {}",
+            pc,
+            disassemble(pc, instr, Some(&emu.symbols))
+        )
+    }
+
+    fn dump_registers(&mut self, emu: &Emulator) -> std::io::Result<()> {
+        let gprs = [
+            Register::Rr0,
+            Register::Rr1,
+            Register::Rr2,
+            Register::Rr3,
+            Register::Rr4,
+            Register::Rr5,
+            Register::Rr6,
+            Register::Rr7,
+        ];
+        for (i, reg) in gprs.into_iter().enumerate() {
+            write!(self.output, "R{} x{:04X}  ", i, emu.register(reg))?;
+            if i % 4 == 3 {
+                writeln!(self.output)?;
+            }
+        }
+
+        let cond = emu.register(Register::Rcond);
+        let flag = match cond {
+            0x4 => "N",
+            0x2 => "Z",
+            0x1 => "P",
+            _ => "?",
+        };
+        let mode = if emu.registers.user_mode() {
+            "user"
+        } else {
+            "supervisor"
+        };
+        writeln!(
+            self.output,
+            "PC x{:04X}  PSR x{:04X} ({}, PL{}, {})",
+            emu.register(Register::Rpc),
+            emu.register(Register::Rpsr),
+            mode,
+            emu.registers.priority(),
+            flag
+        )
+    }
+
+    fn dump_memory(&mut self, emu: &Emulator, start: u16, count: usize) -> std::io::Result<()> {
+        for row in (0..count).step_by(8) {
+            let address = start.wrapping_add(row as u16);
+            write!(self.output, "x{:04X}:", address)?;
+            for i in row..count.min(row + 8) {
+                let value = emu.memory.peek(start.wrapping_add(i as u16) as usize);
+                write!(self.output, " x{:04X}", value)?;
+            }
+            writeln!(self.output)?;
+        }
+        Ok(())
+    }
+
+    fn set(&mut self, emu: &mut Emulator, target: &str, value: &str) -> std::io::Result<()> {
+        let value = match parse_number(value) {
+            Some(v) if (-0x8000..=0xFFFF).contains(&v) => v as u16,
+            _ => return writeln!(self.output, "Invalid value '{}'", value),
+        };
+
+        let reg = match target.to_uppercase().as_str() {
+            "R0" => Some(Register::Rr0),
+            "R1" => Some(Register::Rr1),
+            "R2" => Some(Register::Rr2),
+            "R3" => Some(Register::Rr3),
+            "R4" => Some(Register::Rr4),
+            "R5" => Some(Register::Rr5),
+            "R6" => Some(Register::Rr6),
+            "R7" => Some(Register::Rr7),
+            "PC" => Some(Register::Rpc),
+            "COND" => Some(Register::Rcond),
+            "PSR" => Some(Register::Rpsr),
+            "SSP" => Some(Register::RsavedSsp),
+            "USP" => Some(Register::RsavedUsp),
+            _ => None,
+        };
+
+        if let Some(reg) = reg {
+            emu.set_register(reg, value);
+            return writeln!(self.output, "{} = x{:04X}", target.to_uppercase(), value);
+        }
+
+        match self.resolve(emu, target) {
+            Some(address) => {
+                emu.write_memory(address, value);
+                writeln!(self.output, "[x{:04X}] = x{:04X}", address, value)
+            }
+            None => writeln!(self.output, "Unknown register or address '{}'", target),
+        }
+    }
+}
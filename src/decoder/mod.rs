@@ -0,0 +1,215 @@
+use crate::instruction::sign_extend;
+use crate::opcode::Opcode;
+use crate::register::Register;
+
+/// The second source of ADD and AND: a register or a sign-extended
+/// five-bit immediate.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Operand {
+    Register(Register),
+    Immediate(i16),
+}
+
+/// One decoded LC-3 instruction.
+///
+/// Offsets are sign-extended and relative to the incremented PC, or to the
+/// base register for LDR and STR.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Instruction {
+    Br {
+        n: bool,
+        z: bool,
+        p: bool,
+        offset: i16,
+    },
+    Add {
+        dr: Register,
+        sr1: Register,
+        src2: Operand,
+    },
+    Ld {
+        dr: Register,
+        offset: i16,
+    },
+    St {
+        sr: Register,
+        offset: i16,
+    },
+    Jsr {
+        offset: i16,
+    },
+    Jsrr {
+        base: Register,
+    },
+    And {
+        dr: Register,
+        sr1: Register,
+        src2: Operand,
+    },
+    Ldr {
+        dr: Register,
+        base: Register,
+        offset: i16,
+    },
+    Str {
+        sr: Register,
+        base: Register,
+        offset: i16,
+    },
+    Rti,
+    Not {
+        dr: Register,
+        sr: Register,
+    },
+    Ldi {
+        dr: Register,
+        offset: i16,
+    },
+    Sti {
+        sr: Register,
+        offset: i16,
+    },
+    /// Also covers RET, which is JMP R7.
+    Jmp {
+        base: Register,
+    },
+    /// The reserved opcode 1101.
+    Reserved,
+    Lea {
+        dr: Register,
+        offset: i16,
+    },
+    Trap {
+        vector: u8,
+    },
+}
+
+/// Decodes an instruction word. Every word decodes to something; bits the
+/// ISA leaves unused are ignored.
+pub fn decode(word: u16) -> Instruction {
+    let r11 = Register::general(word >> 9);
+    let r8 = Register::general(word >> 6);
+    let offset = |bits: u8| sign_extend(word & ((1 << bits) - 1), bits) as i16;
+
+    // the opcode field is four bits, so every value names an opcode
+    let op = Opcode::try_from(word >> 12).unwrap();
+    match op {
+        Opcode::OpBr => Instruction::Br {
+            n: word & 0x0800 != 0,
+            z: word & 0x0400 != 0,
+            p: word & 0x0200 != 0,
+            offset: offset(9),
+        },
+        Opcode::OpAdd | Opcode::OpAnd => {
+            let src2 = if word & 0x20 != 0 {
+                Operand::Immediate(offset(5))
+            } else {
+                Operand::Register(Register::general(word))
+            };
+            if let Opcode::OpAdd = op {
+                Instruction::Add {
+                    dr: r11,
+                    sr1: r8,
+                    src2,
+                }
+            } else {
+                Instruction::And {
+                    dr: r11,
+                    sr1: r8,
+                    src2,
+                }
+            }
+        }
+        Opcode::OpLd => Instruction::Ld {
+            dr: r11,
+            offset: offset(9),
+        },
+        Opcode::OpSt => Instruction::St {
+            sr: r11,
+            offset: offset(9),
+        },
+        Opcode::OpJsr => {
+            if word & 0x0800 != 0 {
+                Instruction::Jsr { offset: offset(11) }
+            } else {
+                Instruction::Jsrr { base: r8 }
+            }
+        }
+        Opcode::OpLdr => Instruction::Ldr {
+            dr: r11,
+            base: r8,
+            offset: offset(6),
+        },
+        Opcode::OpStr => Instruction::Str {
+            sr: r11,
+            base: r8,
+            offset: offset(6),
+        },
+        Opcode::OpRti => Instruction::Rti,
+        Opcode::OpNot => Instruction::Not { dr: r11, sr: r8 },
+        Opcode::OpLdi => Instruction::Ldi {
+            dr: r11,
+            offset: offset(9),
+        },
+        Opcode::OpSti => Instruction::Sti {
+            sr: r11,
+            offset: offset(9),
+        },
+        Opcode::OpJmp => Instruction::Jmp { base: r8 },
+        Opcode::OpRes => Instruction::Reserved,
+        Opcode::OpLea => Instruction::Lea {
+            dr: r11,
+            offset: offset(9),
+        },
+        Opcode::OpTrap => Instruction::Trap { vector: word as u8 },
+    }
+}
+
+/// Encodes an instruction in its canonical form, with unused bits as the
+/// ISA specifies them. Offsets and immediates are truncated to the width of
+/// their field.
+///
+/// `encode(decode(word)) == word` for every canonically encoded word.
+pub fn encode(instruction: Instruction) -> u16 {
+    let op = |opcode: Opcode| (opcode as u16) << 12;
+    let reg = |register: Register| register as u16 & 0x7;
+    let field = |value: i16, bits: u8| value as u16 & ((1 << bits) - 1);
+    let src2 = |operand: Operand| match operand {
+        Operand::Register(register) => reg(register),
+        Operand::Immediate(imm) => 0x20 | field(imm, 5),
+    };
+
+    match instruction {
+        Instruction::Br { n, z, p, offset } => {
+            op(Opcode::OpBr)
+                | (n as u16) << 11
+                | (z as u16) << 10
+                | (p as u16) << 9
+                | field(offset, 9)
+        }
+        Instruction::Add { dr, sr1, src2: s } => {
+            op(Opcode::OpAdd) | reg(dr) << 9 | reg(sr1) << 6 | src2(s)
+        }
+        Instruction::Ld { dr, offset } => op(Opcode::OpLd) | reg(dr) << 9 | field(offset, 9),
+        Instruction::St { sr, offset } => op(Opcode::OpSt) | reg(sr) << 9 | field(offset, 9),
+        Instruction::Jsr { offset } => op(Opcode::OpJsr) | 1 << 11 | field(offset, 11),
+        Instruction::Jsrr { base } => op(Opcode::OpJsr) | reg(base) << 6,
+        Instruction::And { dr, sr1, src2: s } => {
+            op(Opcode::OpAnd) | reg(dr) << 9 | reg(sr1) << 6 | src2(s)
+        }
+        Instruction::Ldr { dr, base, offset } => {
+            op(Opcode::OpLdr) | reg(dr) << 9 | reg(base) << 6 | field(offset, 6)
+        }
+        Instruction::Str { sr, base, offset } => {
+            op(Opcode::OpStr) | reg(sr) << 9 | reg(base) << 6 | field(offset, 6)
+        }
+        Instruction::Rti => op(Opcode::OpRti),
+        Instruction::Not { dr, sr } => op(Opcode::OpNot) | reg(dr) << 9 | reg(sr) << 6 | 0x3F,
+        Instruction::Ldi { dr, offset } => op(Opcode::OpLdi) | reg(dr) << 9 | field(offset, 9),
+        Instruction::Sti { sr, offset } => op(Opcode::OpSti) | reg(sr) << 9 | field(offset, 9),
+        Instruction::Jmp { base } => op(Opcode::OpJmp) | reg(base) << 6,
+        Instruction::Reserved => op(Opcode::OpRes),
+        Instruction::Lea { dr, offset } => op(Opcode::OpLea) | reg(dr) << 9 | field(offset, 9),
+        Instruction::Trap { vector } => op(Opcode::OpTrap) | vector as u16,
+    }
+}
@@ -0,0 +1,127 @@
+use crate::decoder::{decode, Instruction, Operand};
+use crate::emulator::Mmu;
+use crate::opcode::Trap;
+use crate::register::Register;
+use crate::symbol::SymbolTable;
+
+/// Renders a single instruction word as canonical LC-3 assembly.
+///
+/// `address` is where the word lives in memory and is used to resolve
+/// PC-relative operands to absolute targets, which are shown as `LABEL` or
+/// `LABEL+n` when `symbols` has a label close enough.
+pub fn disassemble(address: u16, word: u16, symbols: Option<&SymbolTable>) -> String {
+    let target =
+        |offset: i16| format_target(address.wrapping_add(1).wrapping_add(offset as u16), symbols);
+
+    match decode(word) {
+        Instruction::Br { n, z, p, offset } => {
+            if !(n || z || p) {
+                return "NOP".to_string();
+            }
+            let mut mnemonic = String::from("BR");
+            if n {
+                mnemonic.push('n');
+            }
+            if z {
+                mnemonic.push('z');
+            }
+            if p {
+                mnemonic.push('p');
+            }
+            format!("{} {}", mnemonic, target(offset))
+        }
+        Instruction::Add { dr, sr1, src2 } => {
+            format!("ADD {}, {}, {}", reg(dr), reg(sr1), operand(src2))
+        }
+        Instruction::And { dr, sr1, src2 } => {
+            format!("AND {}, {}, {}", reg(dr), reg(sr1), operand(src2))
+        }
+        Instruction::Ld { dr, offset } => format!("LD {}, {}", reg(dr), target(offset)),
+        Instruction::St { sr, offset } => format!("ST {}, {}", reg(sr), target(offset)),
+        Instruction::Ldi { dr, offset } => format!("LDI {}, {}", reg(dr), target(offset)),
+        Instruction::Sti { sr, offset } => format!("STI {}, {}", reg(sr), target(offset)),
+        Instruction::Lea { dr, offset } => format!("LEA {}, {}", reg(dr), target(offset)),
+        Instruction::Jsr { offset } => format!("JSR {}", target(offset)),
+        Instruction::Jsrr { base } => format!("JSRR {}", reg(base)),
+        Instruction::Ldr { dr, base, offset } => {
+            format!("LDR {}, {}, #{}", reg(dr), reg(base), offset)
+        }
+        Instruction::Str { sr, base, offset } => {
+            format!("STR {}, {}, #{}", reg(sr), reg(base), offset)
+        }
+        Instruction::Rti => "RTI".to_string(),
+        Instruction::Not { dr, sr } => format!("NOT {}, {}", reg(dr), reg(sr)),
+        Instruction::Jmp {
+            base: Register::Rr7,
+        } => "RET".to_string(),
+        Instruction::Jmp { base } => format!("JMP {}", reg(base)),
+        Instruction::Reserved => fill(word),
+        Instruction::Trap { vector } => match Trap::try_from(vector as u16) {
+            Ok(Trap::TrapGetc) => "GETC".to_string(),
+            Ok(Trap::TrapOut) => "OUT".to_string(),
+            Ok(Trap::TrapPuts) => "PUTS".to_string(),
+            Ok(Trap::TrapIn) => "IN".to_string(),
+            Ok(Trap::TrapPutsp) => "PUTSP".to_string(),
+            Ok(Trap::TrapHalt) => "HALT".to_string(),
+            Err(_) => format!("TRAP x{:02X}", vector),
+        },
+    }
+}
+
+/// Formats one listing line: address, raw word, label and instruction.
+pub fn listing_line(address: u16, word: u16, symbols: Option<&SymbolTable>) -> String {
+    let label = symbols.and_then(|s| s.label_at(address)).unwrap_or("");
+    format!(
+        "x{:04X}  x{:04X}  {:<12} {}",
+        address,
+        word,
+        label,
+        disassemble(address, word, symbols)
+    )
+}
+
+/// Produces a listing for `words` placed consecutively from `origin`.
+pub fn disassemble_words(origin: u16, words: &[u16], symbols: Option<&SymbolTable>) -> Vec<String> {
+    (origin..=u16::MAX)
+        .zip(words)
+        .map(|(address, &word)| listing_line(address, word, symbols))
+        .collect()
+}
+
+/// Produces a listing for `count` words of memory starting at `start`,
+/// without triggering memory-mapped device reads.
+pub fn disassemble_memory(
+    memory: &Mmu,
+    start: u16,
+    count: usize,
+    symbols: Option<&SymbolTable>,
+) -> Vec<String> {
+    (0..count)
+        .map(|i| {
+            let address = start.wrapping_add(i as u16);
+            listing_line(address, memory.peek(address as usize), symbols)
+        })
+        .collect()
+}
+
+fn format_target(address: u16, symbols: Option<&SymbolTable>) -> String {
+    match symbols {
+        Some(symbols) => symbols.describe(address),
+        None => format!("x{:04X}", address),
+    }
+}
+
+fn reg(register: Register) -> String {
+    format!("R{}", register as u16)
+}
+
+fn operand(operand: Operand) -> String {
+    match operand {
+        Operand::Register(register) => reg(register),
+        Operand::Immediate(imm) => format!("#{}", imm),
+    }
+}
+
+fn fill(word: u16) -> String {
+    format!(".FILL x{:04X}", word)
+}
@@ -1,63 +1,732 @@
+use crate::instruction::read_image_file;
 use crate::opcode::*;
 use crate::register::*;
 
-use std::{io::stdin, io::Read};
+use crate::block::{self, BlockCache};
+use crate::console::{self, Console};
+use crate::decoder::{decode, Instruction};
+use crate::error::EmulatorError;
+use crate::loader::{check_overlaps, Image};
+use crate::symbol::SymbolTable;
+use crate::trace::{TraceEntry, Tracer};
+
+use std::io::{self, Read};
+use std::str::FromStr;
+use std::thread;
+use std::time::{Duration, Instant};
 
 pub const PC_START: u16 = 0x3000;
 pub const MEMORY_MAX: usize = 1 << 16;
+/// Initial supervisor stack pointer; the stack grows down from here.
+pub const SSP_START: u16 = 0x3000;
+/// Base of the interrupt vector table (x0100-x01FF).
+pub const INTERRUPT_VECTOR_TABLE: u16 = 0x0100;
+
+/// Exceptions and their entries in the interrupt vector table.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Exception {
+    PrivilegeViolation = 0x00,
+    IllegalOpcode = 0x01,
+}
+
+/// Device interrupts and their entries in the interrupt vector table.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Interrupt {
+    Keyboard = 0x80,
+}
+
+impl Interrupt {
+    /// The priority level (PL0-PL7) the device interrupts at.
+    pub fn priority(self) -> u16 {
+        match self {
+            Interrupt::Keyboard => 4,
+        }
+    }
+}
+
+/// Bounds on a [`run_limited`](Emulator::run_limited) run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RunLimits {
+    pub max_instructions: Option<u64>,
+    pub timeout: Option<Duration>,
+}
+
+/// How [`run`](Emulator::run) and [`run_limited`](Emulator::run_limited)
+/// execute instructions. [`step`](Emulator::step) always interprets.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Engine {
+    /// Decode and execute one instruction at a time.
+    #[default]
+    Interpreter,
+    /// Translate straight-line runs of instructions into blocks and execute
+    /// a whole block at a time; see [`block`](crate::block).
+    Block,
+}
+
+impl FromStr for Engine {
+    type Err = ();
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "interpreter" => Ok(Engine::Interpreter),
+            "block" => Ok(Engine::Block),
+            _ => Err(()),
+        }
+    }
+}
 
-#[derive(Clone)]
+/// How many instructions run between checks of the wall clock.
+const TIMEOUT_CHECK_INTERVAL: u64 = 1024;
+
+/// How long a native GETC or IN sleeps between polls of the console while
+/// a timeout is running.
+const KEY_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// KBSR[15] is set while a key is waiting in KBDR; KBSR[14] lets the
+/// keyboard raise an interrupt when that happens.
+pub const KBSR_READY: u16 = 1 << 15;
+pub const KBSR_INTERRUPT_ENABLE: u16 = 1 << 14;
+/// DSR[15] is set while the display can accept a character.
+pub const DSR_READY: u16 = 1 << 15;
+/// MCR[15] enables the clock; clearing it stops the machine.
+pub const MCR_CLOCK_ENABLE: u16 = 1 << 15;
+/// Start of the page reserved for memory-mapped device registers.
+pub const DEVICE_PAGE: u16 = 0xFE00;
+
+/// A data access made by an instruction, recorded for tracing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryAccess {
+    Read { address: u16, value: u16 },
+    Write { address: u16, value: u16 },
+}
+
+/// Main memory plus the memory-mapped device registers.
+///
+/// Device registers follow their hardware semantics on [`read`](Mmu::read)
+/// and [`write`](Mmu::write): KBSR only lets software change its
+/// interrupt-enable bit, KBDR and DSR are read-only, writing DDR sends the
+/// character to the console, and clearing the clock-enable bit of MCR stops the machine.
+///
+/// Instructions are decoded once when first fetched and the result is kept
+/// until their word is written again.
 pub struct Mmu {
     memory: Vec<u16>,
+    decoded: Vec<Option<Instruction>>,
+    translated: Vec<bool>,
+    stale: Vec<u16>,
+    pub console: Box<dyn Console>,
+    input_exhausted: bool,
+    /// When a wait for a key gives up, and the timeout that set it.
+    deadline: Option<(Instant, Duration)>,
+    timed_out: Option<Duration>,
+    io_error: Option<io::Error>,
+    accesses: Option<Vec<MemoryAccess>>,
+}
+
+impl Default for Mmu {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Mmu {
     pub fn new() -> Self {
+        let mut memory = vec![0; MEMORY_MAX];
+        memory[MemoryMappedRegister::Dsr as usize] = DSR_READY;
+        memory[MemoryMappedRegister::Mcr as usize] = MCR_CLOCK_ENABLE;
         Mmu {
-            memory: vec![0; MEMORY_MAX],
+            memory,
+            decoded: vec![None; MEMORY_MAX],
+            translated: vec![false; MEMORY_MAX],
+            stale: Vec::new(),
+            console: Box::new(console::terminal()),
+            input_exhausted: false,
+            deadline: None,
+            timed_out: None,
+            io_error: None,
+            accesses: None,
         }
     }
 
     pub fn write(&mut self, address: usize, value: u16) {
-        self.memory[address] = value;
+        if let Some(accesses) = &mut self.accesses {
+            accesses.push(MemoryAccess::Write {
+                address: address as u16,
+                value,
+            });
+        }
+        self.invalidate(address);
+        match address {
+            x if x == MemoryMappedRegister::Kbsr as usize => {
+                let kbsr = self.memory[address] & !KBSR_INTERRUPT_ENABLE;
+                self.memory[address] = kbsr | (value & KBSR_INTERRUPT_ENABLE);
+            }
+            x if x == MemoryMappedRegister::Kbdr as usize => {}
+            x if x == MemoryMappedRegister::Dsr as usize => {}
+            x if x == MemoryMappedRegister::Ddr as usize => {
+                self.memory[address] = value;
+                if let Err(err) = self
+                    .console
+                    .write(value as u8)
+                    .and_then(|_| self.console.flush())
+                {
+                    self.io_error = Some(err);
+                }
+            }
+            _ => self.memory[address] = value,
+        }
+    }
+
+    /// Stores a word of a loaded image. Unlike [`write`](Mmu::write) this
+    /// has no device side effects: the device registers keep their state
+    /// and only ordinary memory takes the word.
+    pub fn load(&mut self, address: usize, value: u16) {
+        self.invalidate(address);
+        let device = [
+            MemoryMappedRegister::Kbsr,
+            MemoryMappedRegister::Kbdr,
+            MemoryMappedRegister::Dsr,
+            MemoryMappedRegister::Ddr,
+            MemoryMappedRegister::Mcr,
+        ]
+        .into_iter()
+        .any(|register| register as usize == address);
+        if !device {
+            self.memory[address] = value;
+        }
+    }
+
+    /// Drops the decoded instruction and any translated block at `address`.
+    fn invalidate(&mut self, address: usize) {
+        self.decoded[address] = None;
+        if self.translated[address] {
+            self.translated[address] = false;
+            self.stale.push(address as u16);
+        }
+    }
+
+    /// Fetches the instruction at `address`, returning its word and its
+    /// decoded form. Words outside the device page are decoded the first
+    /// time they are fetched and served from the cache afterwards.
+    #[inline]
+    pub fn fetch(&mut self, address: u16) -> (u16, Instruction) {
+        let index = address as usize;
+        if address >= DEVICE_PAGE {
+            let word = self.read(index);
+            return (word, decode(word));
+        }
+
+        let word = self.memory[index];
+        let instruction = match self.decoded[index] {
+            Some(instruction) => instruction,
+            None => {
+                let instruction = decode(word);
+                self.decoded[index] = Some(instruction);
+                instruction
+            }
+        };
+        (word, instruction)
+    }
+
+    /// Marks `address` as part of a translated block, so that writing it
+    /// shows up in [`take_stale`](Mmu::take_stale).
+    pub fn mark_translated(&mut self, address: u16) {
+        self.translated[address as usize] = true;
+    }
+
+    /// Returns `true` if translated code was written since the last
+    /// [`take_stale`](Mmu::take_stale).
+    pub fn has_stale(&self) -> bool {
+        !self.stale.is_empty()
+    }
+
+    /// Takes the translated addresses written since the last call.
+    pub fn take_stale(&mut self) -> Vec<u16> {
+        std::mem::take(&mut self.stale)
+    }
+
+    /// Returns the stored word without any memory-mapped side effects.
+    pub fn peek(&self, address: usize) -> u16 {
+        self.memory[address]
     }
 
     pub fn read(&mut self, address: usize) -> u16 {
         if address == MemoryMappedRegister::Kbsr as usize {
-            self.handle_keyboard();
+            self.poll_keyboard();
+        } else if address == MemoryMappedRegister::Kbdr as usize {
+            // taking the character consumes it; with none latched, the
+            // program waits for the next one as GETC would
+            if let Some(key) = self.read_key() {
+                self.memory[address] = key as u16;
+            }
+        }
+        if let Some(accesses) = &mut self.accesses {
+            accesses.push(MemoryAccess::Read {
+                address: address as u16,
+                value: self.memory[address],
+            });
         }
-        return self.memory[address];
+        self.memory[address]
     }
 
-    fn handle_keyboard(&mut self) {
-        let mut buf = [0; 1];
-        stdin().read_exact(&mut buf).unwrap();
-        if buf[0] != 0 {
-            self.write(MemoryMappedRegister::Kbsr as usize, 1 << 15);
-            self.write(MemoryMappedRegister::Kbdr as usize, buf[0] as u16);
-        } else {
-            self.write(MemoryMappedRegister::Kbsr as usize, 0);
+    /// Starts recording every [`read`](Mmu::read) and [`write`](Mmu::write),
+    /// discarding anything recorded so far.
+    pub fn record_accesses(&mut self) {
+        self.accesses = Some(Vec::new());
+    }
+
+    /// Stops recording and returns the accesses made since
+    /// [`record_accesses`](Mmu::record_accesses).
+    pub fn take_accesses(&mut self) -> Vec<MemoryAccess> {
+        self.accesses.take().unwrap_or_default()
+    }
+
+    /// Latches a newly typed key into KBDR and sets KBSR[15], unless the
+    /// previous key has not been read yet. Never blocks.
+    pub fn poll_keyboard(&mut self) {
+        let kbsr = MemoryMappedRegister::Kbsr as usize;
+        if self.memory[kbsr] & KBSR_READY != 0 {
+            return;
+        }
+        if let Some(key) = self.console.poll() {
+            self.memory[MemoryMappedRegister::Kbdr as usize] = key as u16;
+            self.memory[kbsr] |= KBSR_READY;
         }
     }
+
+    /// Returns `true` once the program has asked for a key, by reading
+    /// KBDR or through GETC or IN, after the console input ended. Polling
+    /// KBSR only ever reports that no key is ready.
+    pub fn input_exhausted(&self) -> bool {
+        self.input_exhausted
+    }
+
+    /// Returns [`input_exhausted`](Mmu::input_exhausted) and clears it, so
+    /// the end of input is reported once.
+    pub fn take_input_exhausted(&mut self) -> bool {
+        std::mem::take(&mut self.input_exhausted)
+    }
+
+    /// Takes the error from the last failed display write, if any.
+    pub fn take_io_error(&mut self) -> Option<io::Error> {
+        self.io_error.take()
+    }
+
+    /// Returns the timeout that cut short the last wait for a key, if one
+    /// did, and clears it.
+    pub fn take_timed_out(&mut self) -> Option<Duration> {
+        self.timed_out.take()
+    }
+
+    /// Takes the key latched in KBDR, clearing KBSR[15].
+    fn take_latched_key(&mut self) -> Option<u8> {
+        let kbsr = MemoryMappedRegister::Kbsr as usize;
+        if self.memory[kbsr] & KBSR_READY == 0 {
+            return None;
+        }
+        self.memory[kbsr] &= !KBSR_READY;
+        Some(self.memory[MemoryMappedRegister::Kbdr as usize] as u8)
+    }
+
+    /// Waits for a key, taking the one latched in KBDR first if there is
+    /// one.
+    ///
+    /// Returns `None` once keyboard input is exhausted, which
+    /// [`take_input_exhausted`](Mmu::take_input_exhausted) then reports.
+    /// Inside [`Emulator::run_limited`] the console is polled instead of
+    /// blocking on it, and a wait still going when the timeout passes
+    /// returns `None` for [`take_timed_out`](Mmu::take_timed_out) instead.
+    pub fn read_key(&mut self) -> Option<u8> {
+        let key = match self.deadline {
+            None => match self.take_latched_key() {
+                Some(key) => Some(key),
+                None => self.console.read(),
+            },
+            Some((deadline, timeout)) => loop {
+                if let Some(key) = self.take_latched_key().or_else(|| self.console.poll()) {
+                    break Some(key);
+                }
+                // the console only closes once every queued key is taken
+                if self.console.is_closed() {
+                    break None;
+                }
+                if Instant::now() >= deadline {
+                    self.timed_out = Some(timeout);
+                    return None;
+                }
+                thread::sleep(KEY_POLL_INTERVAL);
+            },
+        };
+        if key.is_none() {
+            self.input_exhausted = true;
+        }
+        key
+    }
 }
 
-#[derive(Clone)]
+/// A complete LC-3 machine.
+///
+/// A freshly constructed emulator has zeroed memory, the PC at [`PC_START`]
+/// and runs in user mode at priority 0 with the zero condition flag set,
+/// ready for an image to be loaded.
+///
+/// An emulator is not `Clone`. It owns its console, whose input is read
+/// by a background thread and whose output may be a file or pipe; a copy
+/// would either race the original for keys or interleave its output. To
+/// run the same program twice, build a second emulator and load the image
+/// into it.
 pub struct Emulator {
     pub memory: Mmu,
     pub registers: Registers,
     pub opcodes: Opcodes,
     pub traps: Traps,
+    pub trap_mode: TrapMode,
     pub running: bool,
+    /// Labels used to name addresses in diagnostics, traces and listings.
+    pub symbols: SymbolTable,
+    /// Records every executed instruction when set.
+    pub tracer: Option<Tracer>,
+    /// Selects how [`run`](Emulator::run) executes instructions.
+    pub engine: Engine,
+    /// Blocks translated by the [`Engine::Block`] engine.
+    pub blocks: BlockCache,
+    fault: Option<EmulatorError>,
+}
+
+impl Default for Emulator {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Emulator {
     pub fn new() -> Self {
-        Emulator {
+        let mut emu = Emulator {
             memory: Mmu::new(),
             registers: Registers::new(),
             opcodes: Opcodes::new(),
             traps: Traps::new(),
+            trap_mode: TrapMode::Native,
             running: true,
+            symbols: SymbolTable::new(),
+            tracer: None,
+            engine: Engine::Interpreter,
+            blocks: BlockCache::new(),
+            fault: None,
+        };
+
+        emu.registers.update(
+            Register::Rpsr,
+            PSR_USER_MODE | ConditionFlag::get_cflag_value(ConditionFlag::FlZro),
+        );
+        emu.registers.update(Register::Rpc, PC_START);
+        emu.registers.update(Register::RsavedSsp, SSP_START);
+
+        emu
+    }
+
+    /// Replaces the console used for keyboard and display I/O.
+    pub fn set_console<C: Console + 'static>(&mut self, console: C) {
+        self.memory.console = Box::new(console);
+        self.memory.input_exhausted = false;
+        self.memory.timed_out = None;
+    }
+
+    /// Returns the console used for keyboard and display I/O.
+    pub fn console(&mut self) -> &mut dyn Console {
+        self.memory.console.as_mut()
+    }
+
+    /// Loads a big-endian LC-3 object image into memory and points the PC
+    /// at its origin.
+    pub fn load_image<R: Read>(&mut self, reader: R) -> Result<(), EmulatorError> {
+        let image = read_image_file(reader, self)?;
+        self.registers.update(Register::Rpc, image.origin);
+        Ok(())
+    }
+
+    /// Loads several images into one memory image and points the PC at
+    /// `entry`, or at the first image's origin when no entry is given.
+    /// Nothing is written if any two images overlap.
+    pub fn load_images(
+        &mut self,
+        images: &[Image],
+        entry: Option<u16>,
+    ) -> Result<(), EmulatorError> {
+        check_overlaps(images)?;
+        for image in images {
+            self.load(image);
+        }
+        if let Some(pc) = entry.or(images.first().map(|image| image.origin)) {
+            self.registers.update(Register::Rpc, pc);
+        }
+        Ok(())
+    }
+
+    /// Writes `image` into memory, leaving the PC alone. Words that fall
+    /// on device registers are skipped rather than written to the devices.
+    pub fn load(&mut self, image: &Image) {
+        for (address, &word) in (image.origin as usize..).zip(&image.words) {
+            self.memory.load(address, word);
+        }
+    }
+
+    /// Fetches, decodes and executes a single instruction.
+    ///
+    /// A pending device interrupt with a higher priority than the running
+    /// program is taken first, so the instruction executed is the first one
+    /// of its handler.
+    ///
+    /// Returns the fault that stopped the machine, if the instruction
+    /// caused one; a HALT simply clears [`running`](Emulator::running).
+    pub fn step(&mut self) -> Result<(), EmulatorError> {
+        if self.memory.peek(MemoryMappedRegister::Kbsr as usize) & KBSR_INTERRUPT_ENABLE != 0 {
+            self.memory.poll_keyboard();
+        }
+        if let Some(interrupt) = self.pending_interrupt() {
+            self.interrupt(interrupt);
+            if !self.running {
+                return self.take_fault();
+            }
+        }
+
+        let pc = self.registers.get_value(Register::Rpc);
+        let (instr, instruction) = self.memory.fetch(pc);
+        self.registers.update(Register::Rpc, pc.wrapping_add(1));
+
+        let tracing = self.tracer.as_ref().is_some_and(Tracer::is_active);
+        if tracing {
+            self.registers.take_written();
+            self.memory.record_accesses();
+        }
+
+        Opcodes::call(self, instruction);
+
+        if tracing {
+            self.trace(pc, instr);
+        }
+
+        if self.memory.peek(MemoryMappedRegister::Mcr as usize) & MCR_CLOCK_ENABLE == 0 {
+            self.running = false;
+        }
+        if let Some(err) = self.memory.take_io_error() {
+            self.stop_with_fault(EmulatorError::Io(err));
+        }
+        // an instruction that gave up waiting for a key runs again when the
+        // machine resumes
+        if let Some(timeout) = self.memory.take_timed_out() {
+            self.registers.update(Register::Rpc, pc);
+            self.fault = Some(EmulatorError::Timeout { pc, timeout });
+        } else if self.memory.take_input_exhausted() && self.fault.is_none() {
+            self.registers.update(Register::Rpc, pc);
+            self.stop_with_fault(EmulatorError::EndOfInput {
+                pc,
+                instruction: instr,
+            });
+        }
+        self.take_fault()
+    }
+
+    /// Executes instructions until the machine halts or faults.
+    pub fn run(&mut self) -> Result<(), EmulatorError> {
+        self.run_limited(RunLimits::default())
+    }
+
+    /// Executes instructions until the machine halts, faults, or exceeds
+    /// one of `limits`. Hitting a limit leaves the machine able to resume.
+    ///
+    /// The timeout also cuts short a KBDR read or native GETC or IN waiting
+    /// for a key, leaving the PC on that instruction so it runs again on
+    /// resuming.
+    pub fn run_limited(&mut self, limits: RunLimits) -> Result<(), EmulatorError> {
+        let start = Instant::now();
+        self.memory.deadline = limits
+            .timeout
+            .and_then(|timeout| Some((start.checked_add(timeout)?, timeout)));
+        let result = self.run_from(start, limits);
+        self.memory.deadline = None;
+        result
+    }
+
+    fn run_from(&mut self, start: Instant, limits: RunLimits) -> Result<(), EmulatorError> {
+        let mut executed: u64 = 0;
+        let mut next_check: u64 = 0;
+        while self.running {
+            let pc = self.registers.get_value(Register::Rpc);
+            if let Some(limit) = limits.max_instructions.filter(|&limit| executed >= limit) {
+                return Err(EmulatorError::InstructionLimit { pc, limit });
+            }
+            if executed >= next_check {
+                next_check = executed + TIMEOUT_CHECK_INTERVAL;
+                if let Some(timeout) = limits.timeout.filter(|&t| start.elapsed() >= t) {
+                    return Err(EmulatorError::Timeout { pc, timeout });
+                }
+            }
+            executed += match self.engine {
+                Engine::Interpreter => {
+                    self.step()?;
+                    1
+                }
+                Engine::Block => {
+                    let budget = limits.max_instructions.map_or(u64::MAX, |l| l - executed);
+                    block::execute(self, budget)?
+                }
+            };
         }
+        Ok(())
+    }
+
+    /// Stops the machine; `error` is returned from the current
+    /// [`step`](Emulator::step).
+    pub fn stop_with_fault(&mut self, error: EmulatorError) {
+        self.fault = Some(error);
+        self.running = false;
+    }
+
+    /// Hands what the instruction at `pc` did to the tracer.
+    fn trace(&mut self, pc: u16, instruction: u16) {
+        let written = self.registers.take_written();
+        let entry = TraceEntry {
+            pc,
+            instruction,
+            registers: (0..8)
+                .filter(|&r| written & (1 << r) != 0)
+                .map(|r| (r, self.registers.get_value(Register::general(r))))
+                .collect(),
+            accesses: self.memory.take_accesses(),
+            nzp: self.registers.get_value(Register::Rcond),
+        };
+
+        let result = match &mut self.tracer {
+            Some(tracer) => tracer.record(&entry, Some(&self.symbols)),
+            None => Ok(()),
+        };
+        if let Err(err) = result {
+            self.stop_with_fault(EmulatorError::Io(err));
+        }
+    }
+
+    fn take_fault(&mut self) -> Result<(), EmulatorError> {
+        match self.fault.take() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the highest priority device interrupt that is requested and
+    /// outranks the priority level of the running program.
+    pub fn pending_interrupt(&self) -> Option<Interrupt> {
+        let kbsr = self.memory.peek(MemoryMappedRegister::Kbsr as usize);
+        let keyboard = kbsr & KBSR_READY != 0 && kbsr & KBSR_INTERRUPT_ENABLE != 0;
+
+        [(Interrupt::Keyboard, keyboard)]
+            .into_iter()
+            .filter(|&(interrupt, requested)| {
+                requested && interrupt.priority() > self.registers.priority()
+            })
+            .map(|(interrupt, _)| interrupt)
+            .max_by_key(|interrupt| interrupt.priority())
+    }
+
+    /// Raises `exception`: switches to supervisor mode and the supervisor
+    /// stack, pushes the PSR and PC, and jumps to the handler found in the
+    /// interrupt vector table. The priority level is left unchanged.
+    ///
+    /// With no handler installed the machine stops instead.
+    pub fn raise_exception(&mut self, exception: Exception) {
+        // raised while executing the instruction just fetched
+        let pc = self.registers.get_value(Register::Rpc).wrapping_sub(1);
+        let instruction = self.memory.peek(pc as usize);
+        let priority = self.registers.priority();
+        if !self.enter_handler(exception as u16, priority) {
+            self.stop_with_fault(match exception {
+                Exception::PrivilegeViolation => {
+                    EmulatorError::PrivilegeViolation { pc, instruction }
+                }
+                Exception::IllegalOpcode => EmulatorError::InvalidOpcode { pc, instruction },
+            });
+        }
+    }
+
+    /// Services `interrupt` like an exception, additionally raising the
+    /// priority level to that of the device.
+    ///
+    /// With no handler installed the machine stops instead.
+    pub fn interrupt(&mut self, interrupt: Interrupt) {
+        if !self.enter_handler(interrupt as u16, interrupt.priority()) {
+            self.stop_with_fault(EmulatorError::UnhandledInterrupt {
+                pc: self.registers.get_value(Register::Rpc),
+                vector: interrupt as u8,
+            });
+        }
+    }
+
+    /// Performs the common exception/interrupt entry sequence, returning
+    /// `false` when the vector table holds no handler for `vector`.
+    fn enter_handler(&mut self, vector: u16, priority: u16) -> bool {
+        let handler = self.memory.peek((INTERRUPT_VECTOR_TABLE + vector) as usize);
+        if handler == 0 {
+            return false;
+        }
+
+        let psr = self.registers.get_value(Register::Rpsr);
+        if self.registers.user_mode() {
+            self.registers
+                .update(Register::RsavedUsp, self.registers.get_value(Register::Rr6));
+            self.registers
+                .update(Register::Rr6, self.registers.get_value(Register::RsavedSsp));
+        }
+        self.registers.update(
+            Register::Rpsr,
+            (psr & !(PSR_USER_MODE | PSR_PRIORITY_MASK)) | (priority << 8),
+        );
+
+        self.push(psr);
+        self.push(self.registers.get_value(Register::Rpc));
+        self.registers.update(Register::Rpc, handler);
+        true
+    }
+
+    /// Pushes `value` onto the stack addressed by R6.
+    pub fn push(&mut self, value: u16) {
+        let sp = self.registers.get_value(Register::Rr6).wrapping_sub(1);
+        self.registers.update(Register::Rr6, sp);
+        self.memory.write(sp as usize, value);
+    }
+
+    /// Pops a value from the stack addressed by R6.
+    pub fn pop(&mut self) -> u16 {
+        let sp = self.registers.get_value(Register::Rr6);
+        let value = self.memory.read(sp as usize);
+        self.registers.update(Register::Rr6, sp.wrapping_add(1));
+        value
+    }
+
+    /// Names `address` after the nearest label in [`symbols`](Emulator::symbols),
+    /// such as `LOOP+3`, or as `xNNNN` when there is none.
+    pub fn describe_address(&self, address: u16) -> String {
+        self.symbols.describe(address)
+    }
+
+    /// Returns the current value of `reg`.
+    pub fn register(&self, reg: Register) -> u16 {
+        self.registers.get_value(reg)
+    }
+
+    /// Overwrites the value of `reg`.
+    pub fn set_register(&mut self, reg: Register, value: u16) {
+        self.registers.update(reg, value);
+    }
+
+    /// Reads a word of memory, triggering memory-mapped device behaviour.
+    pub fn read_memory(&mut self, address: u16) -> u16 {
+        self.memory.read(address as usize)
+    }
+
+    /// Writes a word of memory.
+    pub fn write_memory(&mut self, address: u16, value: u16) {
+        self.memory.write(address as usize, value);
     }
 }
@@ -0,0 +1,107 @@
+use crate::loader::LoadError;
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::time::Duration;
+
+/// Everything that can stop the emulator short of a HALT.
+///
+/// Faults raised while executing an instruction carry the address of that
+/// instruction and its encoding, so a diagnostic can point at the culprit.
+#[derive(Debug)]
+pub enum EmulatorError {
+    /// The object image could not be loaded.
+    Load(LoadError),
+    /// An illegal opcode was executed with no exception handler installed.
+    InvalidOpcode { pc: u16, instruction: u16 },
+    /// A TRAP named a vector with no service routine.
+    InvalidTrapVector { pc: u16, instruction: u16 },
+    /// A privileged instruction ran in user mode with no exception handler
+    /// installed.
+    PrivilegeViolation { pc: u16, instruction: u16 },
+    /// A device interrupt was taken with no handler installed.
+    UnhandledInterrupt { pc: u16, vector: u8 },
+    /// The program asked for a key after the console input ended.
+    EndOfInput { pc: u16, instruction: u16 },
+    /// Reading from or writing to the console failed.
+    Io(io::Error),
+    /// The program ran for the allowed number of instructions without
+    /// halting; `pc` is the next instruction it would have executed.
+    InstructionLimit { pc: u16, limit: u64 },
+    /// The program ran for the allowed wall-clock time without halting.
+    Timeout { pc: u16, timeout: Duration },
+}
+
+impl EmulatorError {
+    /// The address of the instruction that caused the fault, if any.
+    pub fn pc(&self) -> Option<u16> {
+        match *self {
+            EmulatorError::InvalidOpcode { pc, .. }
+            | EmulatorError::InvalidTrapVector { pc, .. }
+            | EmulatorError::PrivilegeViolation { pc, .. }
+            | EmulatorError::UnhandledInterrupt { pc, .. }
+            | EmulatorError::EndOfInput { pc, .. }
+            | EmulatorError::InstructionLimit { pc, .. }
+            | EmulatorError::Timeout { pc, .. } => Some(pc),
+            EmulatorError::Load(_) | EmulatorError::Io(_) => None,
+        }
+    }
+
+    /// The encoding of the instruction that caused the fault, if any.
+    pub fn instruction(&self) -> Option<u16> {
+        match *self {
+            EmulatorError::InvalidOpcode { instruction, .. }
+            | EmulatorError::InvalidTrapVector { instruction, .. }
+            | EmulatorError::PrivilegeViolation { instruction, .. }
+            | EmulatorError::EndOfInput { instruction, .. } => Some(instruction),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for EmulatorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EmulatorError::Load(err) => write!(f, "Could not load image: {}", err),
+            EmulatorError::InvalidOpcode { .. } => write!(f, "Invalid instruction"),
+            EmulatorError::InvalidTrapVector { instruction, .. } => {
+                write!(f, "Invalid trap vector x{:02X}", instruction & 0xFF)
+            }
+            EmulatorError::PrivilegeViolation { .. } => write!(f, "Privilege mode violation"),
+            EmulatorError::UnhandledInterrupt { vector, .. } => {
+                write!(f, "No handler for interrupt vector x{:02X}", vector)
+            }
+            EmulatorError::EndOfInput { .. } => write!(f, "End of input"),
+            EmulatorError::Io(err) => write!(f, "I/O error: {}", err),
+            EmulatorError::InstructionLimit { limit, .. } => {
+                write!(f, "Instruction limit of {} reached", limit)
+            }
+            EmulatorError::Timeout { timeout, .. } => {
+                write!(f, "Timed out after {:?}", timeout)
+            }
+        }
+    }
+}
+
+impl Error for EmulatorError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            EmulatorError::Load(err) => Some(err),
+            EmulatorError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for EmulatorError {
+    fn from(err: io::Error) -> Self {
+        EmulatorError::Io(err)
+    }
+}
+
+impl From<LoadError> for EmulatorError {
+    fn from(err: LoadError) -> Self {
+        EmulatorError::Load(err)
+    }
+}
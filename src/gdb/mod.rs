@@ -0,0 +1,368 @@
+//! GDB Remote Serial Protocol stub.
+//!
+//! The LC-3 is word addressed, so memory addresses in `m`, `M`, `Z0` and
+//! `z0` packets are word addresses and every word is transferred as two
+//! bytes, least significant first. Lengths are given in bytes and must be
+//! even; a read that would not fit in one reply or runs past xFFFF returns
+//! fewer words, as the protocol allows, and a write past xFFFF is refused.
+//! Addresses above xFFFF are rejected. The register file exposed by
+//! `g`/`G`/`p`/`P` is R0-R7, PC and PSR (numbers 0-9), each 16 bits wide
+//! and also sent little-endian.
+
+use crate::emulator::{Emulator, MEMORY_MAX};
+use crate::register::Register;
+
+use std::collections::BTreeSet;
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+
+const REGISTERS: [Register; 10] = [
+    Register::Rr0,
+    Register::Rr1,
+    Register::Rr2,
+    Register::Rr3,
+    Register::Rr4,
+    Register::Rr5,
+    Register::Rr6,
+    Register::Rr7,
+    Register::Rpc,
+    Register::Rpsr,
+];
+
+/// Largest packet the stub accepts, in bytes, as advertised in
+/// `qSupported`. Memory reads are cut short to fit a reply in it.
+const PACKET_SIZE: usize = 0x1000;
+
+/// How often a running `continue` checks the socket for a Ctrl-C.
+const INTERRUPT_POLL_INTERVAL: usize = 1024;
+
+/// Reported when the program faults: it exited with status 1.
+const FAULT_REPLY: &str = "W01";
+
+const SIGINT: u8 = 2;
+const SIGTRAP: u8 = 5;
+
+/// Binds `address` and serves a single GDB session on it.
+pub fn listen<A: ToSocketAddrs>(address: A, emu: &mut Emulator) -> io::Result<()> {
+    let listener = TcpListener::bind(address)?;
+    serve(&listener, emu)
+}
+
+/// Accepts one connection on `listener` and serves it until the client
+/// detaches, kills the target or disconnects.
+pub fn serve(listener: &TcpListener, emu: &mut Emulator) -> io::Result<()> {
+    let (stream, _) = listener.accept()?;
+    stream.set_nodelay(true)?;
+    GdbStub::new(stream).run(emu)
+}
+
+/// A connected GDB session.
+pub struct GdbStub {
+    stream: TcpStream,
+    breakpoints: BTreeSet<u16>,
+}
+
+enum Packet {
+    Command(String),
+    Interrupt,
+}
+
+impl GdbStub {
+    pub fn new(stream: TcpStream) -> Self {
+        GdbStub {
+            stream,
+            breakpoints: BTreeSet::new(),
+        }
+    }
+
+    pub fn run(&mut self, emu: &mut Emulator) -> io::Result<()> {
+        loop {
+            let packet = match self.receive()? {
+                Some(Packet::Command(packet)) => packet,
+                Some(Packet::Interrupt) => {
+                    self.send(&stop_reply(SIGINT))?;
+                    continue;
+                }
+                None => return Ok(()),
+            };
+
+            let (command, args) = packet.split_at(packet.chars().next().map_or(0, char::len_utf8));
+            let reply = match command {
+                "?" => self.status(emu),
+                "g" => REGISTERS
+                    .iter()
+                    .map(|&reg| encode_word(emu.register(reg)))
+                    .collect(),
+                "G" => self.write_registers(emu, args),
+                "p" => match parse_hex(args).and_then(|n| REGISTERS.get(n as usize)) {
+                    Some(&reg) => encode_word(emu.register(reg)),
+                    None => "E01".to_string(),
+                },
+                "P" => self.write_register(emu, args),
+                "m" => self.read_memory(emu, args),
+                "M" => self.write_memory(emu, args),
+                "Z" | "z" => self.breakpoint(command == "Z", args),
+                "s" | "c" if !args.is_empty() && parse_address(args).is_none() => "E01".to_string(),
+                "s" => {
+                    if let Some(address) = parse_address(args) {
+                        emu.set_register(Register::Rpc, address);
+                    }
+                    if emu.running && emu.step().is_err() {
+                        FAULT_REPLY.to_string()
+                    } else {
+                        self.status(emu)
+                    }
+                }
+                "c" => {
+                    if let Some(address) = parse_address(args) {
+                        emu.set_register(Register::Rpc, address);
+                    }
+                    self.resume(emu)?
+                }
+                "k" => return Ok(()),
+                "D" => {
+                    self.send("OK")?;
+                    return Ok(());
+                }
+                "H" => "OK".to_string(),
+                "q" if args.starts_with("Supported") => {
+                    format!("PacketSize={:x};swbreak+", PACKET_SIZE)
+                }
+                "q" if args == "Attached" => "1".to_string(),
+                "q" if args == "C" => "QC1".to_string(),
+                _ => String::new(),
+            };
+
+            self.send(&reply)?;
+        }
+    }
+
+    fn status(&self, emu: &Emulator) -> String {
+        if emu.running {
+            stop_reply(SIGTRAP)
+        } else {
+            "W00".to_string()
+        }
+    }
+
+    fn resume(&mut self, emu: &mut Emulator) -> io::Result<String> {
+        let mut executed: usize = 0;
+        while emu.running {
+            if emu.step().is_err() {
+                return Ok(FAULT_REPLY.to_string());
+            }
+            executed += 1;
+
+            if self.breakpoints.contains(&emu.register(Register::Rpc)) {
+                return Ok(stop_reply(SIGTRAP));
+            }
+
+            if executed.is_multiple_of(INTERRUPT_POLL_INTERVAL) && self.interrupted()? {
+                return Ok(stop_reply(SIGINT));
+            }
+        }
+        Ok(self.status(emu))
+    }
+
+    fn interrupted(&mut self) -> io::Result<bool> {
+        let mut byte = [0; 1];
+        self.stream.set_nonblocking(true)?;
+        let result = self.stream.peek(&mut byte);
+        self.stream.set_nonblocking(false)?;
+
+        match result {
+            Ok(1) if byte[0] == 0x03 => {
+                self.stream.read_exact(&mut byte)?;
+                Ok(true)
+            }
+            Ok(_) => Ok(false),
+            Err(err) if err.kind() == ErrorKind::WouldBlock => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn write_registers(&mut self, emu: &mut Emulator, args: &str) -> String {
+        if !args.is_ascii() || args.len() != REGISTERS.len() * 4 {
+            return "E01".to_string();
+        }
+        let values: Option<Vec<u16>> = (0..REGISTERS.len())
+            .map(|i| decode_word(&args[i * 4..i * 4 + 4]))
+            .collect();
+        match values {
+            Some(values) => {
+                for (&reg, value) in REGISTERS.iter().zip(values) {
+                    emu.set_register(reg, value);
+                }
+                "OK".to_string()
+            }
+            None => "E01".to_string(),
+        }
+    }
+
+    fn write_register(&mut self, emu: &mut Emulator, args: &str) -> String {
+        let parsed = args.split_once('=').and_then(|(n, value)| {
+            let reg = REGISTERS.get(parse_hex(n)? as usize)?;
+            Some((*reg, decode_word(value)?))
+        });
+        match parsed {
+            Some((reg, value)) => {
+                emu.set_register(reg, value);
+                "OK".to_string()
+            }
+            None => "E01".to_string(),
+        }
+    }
+
+    fn read_memory(&mut self, emu: &Emulator, args: &str) -> String {
+        match parse_range(args) {
+            // each word takes four hex digits of the reply
+            Some((address, words)) => {
+                let start = address as usize;
+                let end = MEMORY_MAX.min(start + words.min(PACKET_SIZE / 4));
+                (start..end)
+                    .map(|address| encode_word(emu.memory.peek(address)))
+                    .collect()
+            }
+            None => "E01".to_string(),
+        }
+    }
+
+    fn write_memory(&mut self, emu: &mut Emulator, args: &str) -> String {
+        let parsed = args.split_once(':').and_then(|(range, data)| {
+            let (address, words) = parse_range(range)?;
+            if address as usize + words > MEMORY_MAX {
+                return None;
+            }
+            if !data.is_ascii() || data.len() != words * 4 {
+                return None;
+            }
+            let values: Option<Vec<u16>> = (0..words)
+                .map(|i| decode_word(&data[i * 4..i * 4 + 4]))
+                .collect();
+            Some((address, values?))
+        });
+        match parsed {
+            Some((address, values)) => {
+                for (address, value) in (address as usize..).zip(values) {
+                    emu.write_memory(address as u16, value);
+                }
+                "OK".to_string()
+            }
+            None => "E01".to_string(),
+        }
+    }
+
+    fn breakpoint(&mut self, insert: bool, args: &str) -> String {
+        let mut fields = args.split(',');
+        let (kind, address) = match (fields.next(), fields.next().and_then(parse_address)) {
+            (Some(kind), Some(address)) => (kind, address),
+            _ => return "E01".to_string(),
+        };
+        // only software breakpoints are supported
+        if kind != "0" {
+            return String::new();
+        }
+        if insert {
+            self.breakpoints.insert(address);
+        } else {
+            self.breakpoints.remove(&address);
+        }
+        "OK".to_string()
+    }
+
+    /// Reads the next packet, acknowledging it and asking for packets with
+    /// a bad checksum to be resent. Returns `None` once the client
+    /// disconnects.
+    fn receive(&mut self) -> io::Result<Option<Packet>> {
+        let mut byte = [0; 1];
+        loop {
+            loop {
+                if self.stream.read(&mut byte)? == 0 {
+                    return Ok(None);
+                }
+                match byte[0] {
+                    b'$' => break,
+                    0x03 => return Ok(Some(Packet::Interrupt)),
+                    // acknowledgements of our own packets
+                    _ => continue,
+                }
+            }
+
+            let mut data = Vec::new();
+            loop {
+                if self.stream.read(&mut byte)? == 0 {
+                    return Ok(None);
+                }
+                if byte[0] == b'#' {
+                    break;
+                }
+                data.push(byte[0]);
+            }
+
+            let mut checksum = [0; 2];
+            self.stream.read_exact(&mut checksum)?;
+            let expected = std::str::from_utf8(&checksum)
+                .ok()
+                .and_then(|text| u8::from_str_radix(text, 16).ok());
+
+            if expected != Some(checksum_of(&data)) {
+                self.stream.write_all(b"-")?;
+                continue;
+            }
+            self.stream.write_all(b"+")?;
+
+            return Ok(Some(Packet::Command(
+                String::from_utf8_lossy(&data).into_owned(),
+            )));
+        }
+    }
+
+    fn send(&mut self, data: &str) -> io::Result<()> {
+        let packet = format!("${}#{:02x}", data, checksum_of(data.as_bytes()));
+        self.stream.write_all(packet.as_bytes())?;
+        self.stream.flush()
+    }
+}
+
+fn stop_reply(signal: u8) -> String {
+    format!("S{:02x}", signal)
+}
+
+fn checksum_of(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |sum, &b| sum.wrapping_add(b))
+}
+
+fn parse_hex(text: &str) -> Option<u32> {
+    u32::from_str_radix(text, 16).ok()
+}
+
+/// Parses a word address, rejecting anything past xFFFF.
+fn parse_address(text: &str) -> Option<u16> {
+    parse_hex(text).and_then(|address| u16::try_from(address).ok())
+}
+
+/// Parses `addr,length` into a word address and a number of words. The
+/// length may cover at most the whole address space.
+fn parse_range(text: &str) -> Option<(u16, usize)> {
+    let (address, length) = text.split_once(',')?;
+    let address = parse_address(address)?;
+    let length = parse_hex(length)? as usize;
+    if !length.is_multiple_of(2) || length / 2 > MEMORY_MAX {
+        return None;
+    }
+    Some((address, length / 2))
+}
+
+fn encode_word(value: u16) -> String {
+    let [lo, hi] = value.to_le_bytes();
+    format!("{:02x}{:02x}", lo, hi)
+}
+
+fn decode_word(text: &str) -> Option<u16> {
+    if !text.is_ascii() || text.len() != 4 {
+        return None;
+    }
+    let lo = u8::from_str_radix(&text[0..2], 16).ok()?;
+    let hi = u8::from_str_radix(&text[2..4], 16).ok()?;
+    Some(u16::from_le_bytes([lo, hi]))
+}
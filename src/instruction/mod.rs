@@ -1,91 +1,114 @@
+use crate::decoder::Operand;
 use crate::emulator::*;
-use crate::opcode::Trap;
+use crate::error::EmulatorError;
+use crate::loader::Image;
+use crate::opcode::{Trap, TrapMode, Traps};
 use crate::register::*;
 
-use std::{
-    fs::File,
-    io::{stdin, stdout, BufReader, Read, Write},
-    process::exit,
-};
+use std::io::{BufReader, Read};
 
 pub fn help() {
     println!(
-        "Usage: lc3_emu <binary>
+        "Usage: lc3_emu [--os] [--headless] [--input <file>] [--output <file>]
+              [--format <format>] [--origin <address>] [--entry <address>]
+              [--symbols <file>] [--trace <file> [--trace-range <start>:<end>]
+              [--trace-limit <n>]] [--max-instructions <n>] [--timeout <seconds>]
+              [--engine <engine>] [--debug | --gdb <address>] <binary>...
+       lc3_emu asm <source> [-o <output>]
+       lc3_emu disasm <object> [--symbols <file>]
 
         Options:
-            <binary>    Binary to emulate."
+            <binary>    Object files to load. They must not overlap.
+            --format    Image format: obj (big-endian, the default), hex
+                        (one hex word per line), bin (one 16-digit binary
+                        word per line), raw-le or raw-be. Files ending in
+                        .hex or .bin are detected automatically.
+            --origin    Load a raw-le or raw-be image, which has no origin
+                        word, at <address>. Required for raw images and only
+                        accepted with a single binary.
+            --entry     Start executing at <address> instead of the origin
+                        of the first binary.
+            --os        Service TRAPs with the bundled LC-3 operating system
+                        image instead of the built-in native routines.
+            --headless  Run without touching the terminal, for pipes and
+                        CI. Exits with 0 after HALT and 1 after a fault.
+            --input     Read keyboard input from <file> instead of stdin.
+            --output    Write display output to <file> instead of stdout.
+            --symbols   Name addresses in diagnostics, listings and the
+                        debugger after the labels in a .sym file, as in
+                        LOOP+3. The .sym next to each binary is read too.
+            --trace     Log every executed instruction to <file>: PC, word,
+                        mnemonic, registers and memory written, memory
+                        read, and the resulting condition codes.
+            --trace-range
+                        Only trace instructions between two addresses,
+                        for example x3000:x30FF.
+            --trace-limit
+                        Stop tracing after <n> entries.
+            --max-instructions
+                        Stop after executing <n> instructions.
+            --timeout   Stop after <seconds> of wall-clock time. Both limits
+                        exit with status 3 and report the PC and registers.
+            --engine    interpreter (the default) executes one instruction at
+                        a time; block translates straight-line code into
+                        blocks for faster runs.
+            --debug     Start an interactive debugger instead of running
+                        the binary; type 'help' at its prompt. The
+                        program reads its input from --input.
+            --gdb       Serve the GDB remote protocol on <address>, for
+                        example 127.0.0.1:1234.
+
+            Options that take a value also accept --option=value.
+
+        Commands:
+            asm         Assemble <source> into an object file and a symbol
+                        table. The output defaults to <source> with an .obj
+                        extension.
+            disasm      Print a listing of the instructions in <object>."
     );
 }
 
-pub fn read_n<R>(reader: R, bytes_to_read: u64) -> Vec<u8>
-where
-    R: Read,
-{
-    let mut buf = vec![];
-    let mut chunk = reader.take(bytes_to_read);
-    chunk.read_to_end(&mut buf).expect("Not enough bytes read");
-    buf
+/// Reads a big-endian object image and writes it into memory at its origin.
+pub fn read_image_file<R: Read>(file: R, emu: &mut Emulator) -> Result<Image, EmulatorError> {
+    let image = Image::read_object(BufReader::new(file))?;
+    emu.load(&image);
+    Ok(image)
 }
 
-pub fn be_to_le(buf: &mut Vec<u8>) {
-    let mut tmp: u8;
-    for i in (0..buf.len()).step_by(2) {
-        tmp = buf[i + 1];
-        buf[i + 1] = buf[i];
-        buf[i] = tmp;
+pub fn sign_extend(mut x: u16, bit_count: u8) -> u16 {
+    if (x >> (bit_count - 1)) & 1 != 0 {
+        x |= 0xFFFF << bit_count;
     }
+    x
 }
 
-pub fn vec_u8_to_vec_u16(buf: Vec<u8>) -> Vec<u16> {
-    let mut other: Vec<u16> = vec![0; buf.len() / 2];
-    let mut j = 0;
-
-    for i in (0..buf.len()).step_by(2) {
-        other[j] = ((buf[i + 1] as u16) << 8) | buf[i] as u16;
-        j += 1;
-    }
-
-    other
+/// Adds two words the way the LC-3 adder does, wrapping at 16 bits. Every
+/// address and ALU computation goes through this, so offsets that reach
+/// past x0000 or xFFFF behave the same in debug and release builds.
+pub fn wrapping_add(a: u16, b: u16) -> u16 {
+    a.wrapping_add(b)
 }
 
-pub fn read_image_file(file: File, emu: &mut Emulator) {
-    let mut reader = BufReader::new(file);
-
-    // origin seems to be the PC_START
-    let mut origin = read_n(reader.by_ref(), 2);
-    be_to_le(&mut origin);
-
-    let mut rest = read_n(
-        reader.by_ref(),
-        (MEMORY_MAX - origin.len()).try_into().unwrap(),
-    );
-    be_to_le(&mut rest);
-
-    let tmp: String = String::from_utf8(origin).unwrap();
-
-    let buf = vec_u8_to_vec_u16(rest);
-    let pc_start = match tmp.parse::<usize>() {
-        Ok(i) => i,
-        Err(_e) => PC_START as usize,
-    };
-
-    let mut address: usize = pc_start;
+/// `PC + SEXT(offset)`, for the PC-relative addressing mode.
+fn pc_relative(emu: &Emulator, offset: i16) -> u16 {
+    wrapping_add(emu.registers.get_value(Register::Rpc), offset as u16)
+}
 
-    for i in buf.clone() {
-        emu.memory.write(address, i);
-        address += 1;
-    }
+/// `BaseR + SEXT(offset)`, for the base+offset addressing mode.
+fn base_relative(emu: &Emulator, base: Register, offset: i16) -> u16 {
+    wrapping_add(emu.registers.get_value(base), offset as u16)
 }
 
-pub fn sign_extend(mut x: u16, bit_count: u8) -> u16 {
-    if (x >> (bit_count - 1)) & 1 != 0 {
-        x |= 0xFFFF << bit_count;
+/// The value of the second source of ADD or AND.
+fn operand_value(emu: &Emulator, operand: Operand) -> u16 {
+    match operand {
+        Operand::Register(reg) => emu.registers.get_value(reg),
+        Operand::Immediate(imm) => imm as u16,
     }
-    x
 }
 
-pub fn update_flags(emu: &mut Emulator, reg: u16) {
-    let r: u16 = emu.registers.get_value(Register::try_from(reg).unwrap());
+pub fn update_flags(emu: &mut Emulator, reg: Register) {
+    let r: u16 = emu.registers.get_value(reg);
 
     if r == 0 {
         emu.registers.update(
@@ -105,278 +128,251 @@ pub fn update_flags(emu: &mut Emulator, reg: u16) {
     }
 }
 
-pub fn op_add(emu: &mut Emulator, instr: u16) {
-    let dr: u16 = (instr >> 9) & 0x7;
-    let sr1: u16 = (instr >> 6) & 0x7;
-    let r1: u16 = emu.registers.get_value(Register::try_from(sr1).unwrap());
-    let imm_flag: u16 = (instr >> 5) & 0x1;
+pub fn op_add(emu: &mut Emulator, dr: Register, sr1: Register, src2: Operand) {
+    let r1: u16 = emu.registers.get_value(sr1);
+    let r2: u16 = operand_value(emu, src2);
 
-    if imm_flag == 0 {
-        let sr2: u16 = instr & 0x7;
-        let r2: u16 = emu.registers.get_value(Register::try_from(sr2).unwrap());
-        let val: u32 = r1 as u32 + r2 as u32;
-
-        emu.registers
-            .update(Register::try_from(dr).unwrap(), val as u16);
-    } else {
-        let imm5: u16 = sign_extend(instr & 0x1F, 5);
-        let val: u32 = r1 as u32 + imm5 as u32;
-
-        emu.registers
-            .update(Register::try_from(dr).unwrap(), val as u16);
-    }
+    emu.registers.update(dr, wrapping_add(r1, r2));
 
     update_flags(emu, dr);
 }
 
-pub fn op_and(emu: &mut Emulator, instr: u16) {
-    let dr: u16 = (instr >> 9) & 0x7;
-    let sr1: u16 = (instr >> 6) & 0x7;
-    let r1: u16 = emu.registers.get_value(Register::try_from(sr1).unwrap());
-    let imm_flag: u16 = instr & 0x20;
+pub fn op_and(emu: &mut Emulator, dr: Register, sr1: Register, src2: Operand) {
+    let r1: u16 = emu.registers.get_value(sr1);
+    let r2: u16 = operand_value(emu, src2);
 
-    if imm_flag == 0 {
-        let sr2: u16 = instr & 0x7;
-        let r2: u16 = emu.registers.get_value(Register::try_from(sr2).unwrap());
-        emu.registers
-            .update(Register::try_from(dr).unwrap(), r1 & r2);
-    } else {
-        let imm5: u16 = sign_extend(instr & 0x1F, 5);
-        emu.registers
-            .update(Register::try_from(dr).unwrap(), r1 & imm5);
-    }
+    emu.registers.update(dr, r1 & r2);
 
     update_flags(emu, dr);
 }
 
-pub fn op_jmp(emu: &mut Emulator, instr: u16) {
-    let base_r: u16 = (instr >> 6) & 0x7;
-    emu.registers.update(
-        Register::Rpc,
-        emu.registers.get_value(Register::try_from(base_r).unwrap()),
-    );
+pub fn op_jmp(emu: &mut Emulator, base: Register) {
+    emu.registers
+        .update(Register::Rpc, emu.registers.get_value(base));
 }
 
-pub fn op_res() {}
+pub fn op_res(emu: &mut Emulator) {
+    emu.raise_exception(Exception::IllegalOpcode);
+}
 
-pub fn op_jsr(emu: &mut Emulator, instr: u16) {
-    emu.registers
-        .update(Register::Rr7, emu.registers.get_value(Register::Rpc));
+pub fn op_jsr(emu: &mut Emulator, offset: i16) {
+    let return_address = emu.registers.get_value(Register::Rpc);
 
-    let flag = (instr >> 11) & 1;
+    emu.registers
+        .update(Register::Rpc, pc_relative(emu, offset));
+    emu.registers.update(Register::Rr7, return_address);
+}
 
-    if flag != 0 {
-        let pc_offset: u16 = sign_extend(instr & 0x7FF, 11);
-        let value: u32 = emu.registers.get_value(Register::Rpc) as u32 + pc_offset as u32;
+pub fn op_jsrr(emu: &mut Emulator, base: Register) {
+    let return_address = emu.registers.get_value(Register::Rpc);
 
-        emu.registers.update(Register::Rpc, value as u16);
-    } else {
-        let base_r: u16 = (instr >> 6) & 0x7;
-
-        emu.registers.update(Register::Rpc, base_r);
-    }
+    // read BaseR before R7 is overwritten, so JSRR R7 works
+    emu.registers
+        .update(Register::Rpc, emu.registers.get_value(base));
+    emu.registers.update(Register::Rr7, return_address);
 }
 
-pub fn op_br(emu: &mut Emulator, instr: u16) {
-    let cond_flag: u16 = (instr >> 9) & 0x7;
-    let pc_offset: u16 = sign_extend(instr & 0x1FF, 9);
+pub fn op_br(emu: &mut Emulator, n: bool, z: bool, p: bool, offset: i16) {
+    let cond_flag: u16 = (n as u16) << 2 | (z as u16) << 1 | p as u16;
     let r_cond = emu.registers.get_value(Register::Rcond);
 
     if cond_flag & r_cond != 0 {
-        let val: u32 = emu.registers.get_value(Register::Rpc) as u32 + pc_offset as u32;
-        emu.registers.update(Register::Rpc, val as u16);
+        emu.registers
+            .update(Register::Rpc, pc_relative(emu, offset));
     }
 }
 
-pub fn op_ld(emu: &mut Emulator, instr: u16) {
-    let dr: u16 = (instr >> 9) & 0x7;
-    let pc_offset: u16 = sign_extend(instr & 0x1FF, 9);
-    let mem: u32 = Register::Rpc as u32 + pc_offset as u32;
-
-    let value = emu.memory.read(mem as usize);
+pub fn op_ld(emu: &mut Emulator, dr: Register, offset: i16) {
+    let value = emu.read_memory(pc_relative(emu, offset));
 
-    emu.registers.update(Register::try_from(dr).unwrap(), value);
+    emu.registers.update(dr, value);
 
     update_flags(emu, dr);
 }
 
-pub fn op_ldi(emu: &mut Emulator, instr: u16) {
-    let dr: u16 = (instr >> 9) & 0x7;
-    let pc_offset: u16 = sign_extend(instr & 0x1FF, 9);
+pub fn op_ldi(emu: &mut Emulator, dr: Register, offset: i16) {
+    let address: u16 = emu.read_memory(pc_relative(emu, offset));
+    let value = emu.read_memory(address);
 
-    let x: u16 = emu
-        .memory
-        .read((emu.registers.get_value(Register::Rpc) as u16 + pc_offset).into());
-    emu.registers
-        .update(Register::try_from(dr).unwrap(), emu.memory.read(x.into()));
+    emu.registers.update(dr, value);
 
     update_flags(emu, dr);
 }
 
-pub fn op_ldr(emu: &mut Emulator, instr: u16) {
-    let dr: u16 = (instr >> 9) & 0x7;
-    let base_r: u16 = (instr >> 6) & 0x7;
-    let offset: u16 = sign_extend(instr & 0x3F, 6);
-    let value: u32 = Register::try_from(base_r).unwrap() as u32 + offset as u32;
+pub fn op_ldr(emu: &mut Emulator, dr: Register, base: Register, offset: i16) {
+    let value = emu.read_memory(base_relative(emu, base, offset));
 
-    emu.registers.update(
-        Register::try_from(dr).unwrap(),
-        emu.memory.read(value as usize),
-    );
+    emu.registers.update(dr, value);
 
     update_flags(emu, dr);
 }
 
-pub fn op_lea(emu: &mut Emulator, instr: u16) {
-    let dr: u16 = (instr >> 9) & 0x7;
-    let pc_offset: u16 = sign_extend(instr & 0x1FF, 9);
-    let value: u32 = emu.registers.get_value(Register::Rpc) as u32 + pc_offset as u32;
-
-    emu.registers
-        .update(Register::try_from(dr).unwrap(), value as u16);
+pub fn op_lea(emu: &mut Emulator, dr: Register, offset: i16) {
+    emu.registers.update(dr, pc_relative(emu, offset));
 
     update_flags(emu, dr);
 }
 
-pub fn op_rti() {}
-
-pub fn op_not(emu: &mut Emulator, instr: u16) {
-    let dr: u16 = (instr >> 9) & 0x7;
-    let sr: u16 = (instr >> 6) & 0x7;
+pub fn op_rti(emu: &mut Emulator) {
+    if emu.registers.user_mode() {
+        emu.raise_exception(Exception::PrivilegeViolation);
+        return;
+    }
 
-    emu.registers.update(
-        Register::try_from(dr).unwrap(),
-        !emu.registers.get_value(Register::try_from(sr).unwrap()),
-    );
+    let pc = emu.pop();
+    let psr = emu.pop();
+    emu.registers.update(Register::Rpc, pc);
+    emu.registers.update(Register::Rpsr, psr);
 
-    update_flags(emu, dr);
+    if emu.registers.user_mode() {
+        emu.registers
+            .update(Register::RsavedSsp, emu.registers.get_value(Register::Rr6));
+        emu.registers
+            .update(Register::Rr6, emu.registers.get_value(Register::RsavedUsp));
+    }
 }
 
-pub fn op_st(emu: &mut Emulator, instr: u16) {
-    let sr: u16 = (instr >> 9) & 0x7;
-    let pc_offset: u16 = sign_extend(instr & 0x1FF, 9);
-    let value: u32 = emu.registers.get_value(Register::Rpc) as u32 + pc_offset as u32;
-    let value: u16 = value as u16;
+pub fn op_not(emu: &mut Emulator, dr: Register, sr: Register) {
+    emu.registers.update(dr, !emu.registers.get_value(sr));
 
-    emu.memory.write(
-        value as usize,
-        emu.registers.get_value(Register::try_from(sr).unwrap()),
-    );
+    update_flags(emu, dr);
 }
 
-pub fn op_sti(emu: &mut Emulator, instr: u16) {
-    let sr: u16 = (instr >> 9) & 0x7;
-    let pc_offset: u16 = sign_extend(instr & 0x1FF, 9);
-
-    let value: u32 = emu.registers.get_value(Register::Rpc) as u32 + pc_offset as u32;
-    let value: u16 = value as u16;
-    let address: usize = emu.memory.read(value as usize) as usize;
-
-    emu.memory.write(
-        address,
-        emu.registers.get_value(Register::try_from(sr).unwrap()),
-    );
+pub fn op_st(emu: &mut Emulator, sr: Register, offset: i16) {
+    emu.write_memory(pc_relative(emu, offset), emu.registers.get_value(sr));
 }
 
-pub fn op_str(emu: &mut Emulator, instr: u16) {
-    let sr: u16 = (instr >> 9) & 0x7;
-    let base_r: u16 = (instr >> 6) & 0x7;
-    let offset: u16 = sign_extend(instr & 0x3F, 6);
+pub fn op_sti(emu: &mut Emulator, sr: Register, offset: i16) {
+    let address: u16 = emu.read_memory(pc_relative(emu, offset));
 
-    let value: u32 =
-        emu.registers.get_value(Register::try_from(base_r).unwrap()) as u32 + offset as u32;
-    let value: u16 = value as u16;
+    emu.write_memory(address, emu.registers.get_value(sr));
+}
 
-    emu.memory.write(
-        value as usize,
-        emu.registers.get_value(Register::try_from(sr).unwrap()),
+pub fn op_str(emu: &mut Emulator, sr: Register, base: Register, offset: i16) {
+    emu.write_memory(
+        base_relative(emu, base, offset),
+        emu.registers.get_value(sr),
     );
 }
 
-pub fn op_trap(emu: &mut Emulator, instr: u16) {
-    let trap = Trap::try_from(instr & 0xFF);
-    if let Ok(trap) = trap {
-        emu.traps.clone().call(trap, emu);
+pub fn op_trap(emu: &mut Emulator, vector: u8) {
+    let pc = emu.registers.get_value(Register::Rpc).wrapping_sub(1);
+    let invalid = EmulatorError::InvalidTrapVector {
+        pc,
+        instruction: emu.memory.peek(pc as usize),
+    };
+
+    match emu.trap_mode {
+        TrapMode::Native => match Trap::try_from(vector as u16) {
+            Ok(trap) => Traps::call(emu, trap),
+            Err(()) => emu.stop_with_fault(invalid),
+        },
+        TrapMode::Os => {
+            let routine = emu.read_memory(vector as u16);
+            if routine == 0 {
+                emu.stop_with_fault(invalid);
+                return;
+            }
+            emu.registers
+                .update(Register::Rr7, emu.registers.get_value(Register::Rpc));
+            emu.registers.update(Register::Rpc, routine);
+        }
     }
 }
 
 pub fn trap_getc(emu: &mut Emulator) {
-    let value: u16 = stdin()
-        .bytes()
-        .next()
-        .and_then(|result| result.ok())
-        .map(|byte| byte as u16)
-        .unwrap();
+    let value: u16 = match emu.memory.read_key() {
+        Some(key) => key as u16,
+        None => return,
+    };
 
     emu.registers.update(Register::Rr0, value);
     // update_flags(emu, 0);
 }
 
+fn put_char(emu: &mut Emulator, c: u8) {
+    if let Err(err) = emu.memory.console.write(c) {
+        emu.stop_with_fault(err.into());
+    }
+}
+
+fn put_str(emu: &mut Emulator, s: &str) {
+    for c in s.bytes() {
+        put_char(emu, c);
+    }
+}
+
+fn flush(emu: &mut Emulator) {
+    if let Err(err) = emu.memory.console.flush() {
+        emu.stop_with_fault(err.into());
+    }
+}
+
 pub fn trap_out(emu: &mut Emulator) {
     let c: u8 = emu.registers.get_value(Register::Rr0) as u8;
-    println!("{:?}", c as char);
+    put_char(emu, c);
+    flush(emu);
 }
 
 pub fn trap_puts(emu: &mut Emulator) {
-    let mut i: usize = (emu.registers.get_value(Register::Rr0) as u16).into();
-    let mut c: u16 = emu.memory.read(i);
+    let mut i: u16 = emu.registers.get_value(Register::Rr0);
+    let mut c: u16 = emu.read_memory(i);
 
-    loop {
+    // a string with no terminator ends after one pass around memory
+    for _ in 0..MEMORY_MAX {
         if c == 0 {
             break;
         }
 
-        print!("{}", c as u8 as char);
-        i += 1;
-        c = emu.memory.read(i);
+        put_char(emu, c as u8);
+        i = wrapping_add(i, 1);
+        c = emu.read_memory(i);
     }
 
-    stdout().flush().expect("Failed to flush");
+    flush(emu);
 }
 
 pub fn trap_in(emu: &mut Emulator) {
-    println!("Enter a character: ");
-    stdout().flush().expect("Failed to flush");
+    put_str(emu, "Enter a character: \n");
+    flush(emu);
 
-    let value: u16 = stdin()
-        .bytes()
-        .next()
-        .and_then(|result| result.ok())
-        .map(|byte| byte as u16)
-        .unwrap();
+    let value: u16 = match emu.memory.read_key() {
+        Some(key) => key as u16,
+        None => return,
+    };
 
     emu.registers.update(Register::Rr0, value);
     // update_flags(emu, 0);
 }
 
 pub fn trap_putsp(emu: &mut Emulator) {
-    let mut i: usize = (emu.registers.get_value(Register::Rr0) as u16).into();
-    let mut c: u16 = emu.memory.read(i);
+    let mut i: u16 = emu.registers.get_value(Register::Rr0);
+    let mut c: u16 = emu.read_memory(i);
 
     let mut c1: u8;
     let mut c2: u8;
 
-    loop {
+    for _ in 0..MEMORY_MAX {
         if c == 0 {
             break;
         }
 
         c1 = c as u8;
-        print!("{}", c1 as char);
+        put_char(emu, c1);
 
         c2 = (c >> 8) as u8;
         if c2 != 0 {
-            print!("{}", c2 as char);
+            put_char(emu, c2);
         }
 
-        i += 1;
-        c = emu.memory.read(i);
+        i = wrapping_add(i, 1);
+        c = emu.read_memory(i);
     }
-    stdout().flush().expect("Failed to flush");
+    flush(emu);
 }
 
 pub fn trap_halt(emu: &mut Emulator) {
-    println!("HALT");
-    stdout().flush().expect("Failed to flush");
+    put_str(emu, "HALT\n");
+    flush(emu);
     emu.running = false;
 }
@@ -0,0 +1,89 @@
+use std::io::{stdin, ErrorKind, Read};
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+use std::thread;
+
+/// Keyboard input that can be polled without blocking.
+///
+/// A background thread reads the input source one byte at a time and
+/// queues each key, so checking for a key never stalls the emulator. The
+/// thread is only started on first use, leaving the source untouched for
+/// programs that never read the keyboard.
+pub struct Keyboard {
+    source: Option<Box<dyn Read + Send>>,
+    receiver: Option<Receiver<u8>>,
+    closed: bool,
+}
+
+impl Default for Keyboard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Keyboard {
+    /// A keyboard reading from the process's standard input.
+    pub fn new() -> Self {
+        Self::from_reader(stdin())
+    }
+
+    /// A keyboard reading from `source`.
+    pub fn from_reader<R: Read + Send + 'static>(source: R) -> Self {
+        Keyboard {
+            source: Some(Box::new(source)),
+            receiver: None,
+            closed: false,
+        }
+    }
+
+    /// Returns the next key if one has been typed.
+    pub fn poll(&mut self) -> Option<u8> {
+        match self.receiver().try_recv() {
+            Ok(key) => Some(key),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => {
+                self.closed = true;
+                None
+            }
+        }
+    }
+
+    /// Waits for the next key. Returns `None` once the input is exhausted.
+    pub fn read(&mut self) -> Option<u8> {
+        let key = self.receiver().recv().ok();
+        if key.is_none() {
+            self.closed = true;
+        }
+        key
+    }
+
+    /// Returns `true` once the input source has ended and every queued key
+    /// has been consumed.
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    fn receiver(&mut self) -> &Receiver<u8> {
+        if self.receiver.is_none() {
+            let (sender, receiver) = channel();
+            if let Some(mut source) = self.source.take() {
+                thread::spawn(move || {
+                    let mut buf = [0; 1];
+                    loop {
+                        match source.read(&mut buf) {
+                            Ok(0) => break,
+                            Ok(_) => {
+                                if sender.send(buf[0]).is_err() {
+                                    break;
+                                }
+                            }
+                            Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                            Err(_) => break,
+                        }
+                    }
+                });
+            }
+            self.receiver = Some(receiver);
+        }
+        self.receiver.as_ref().unwrap()
+    }
+}
@@ -0,0 +1,57 @@
+//! Core of the LC-3 emulator.
+//!
+//! The [`Emulator`] type owns the machine state (memory, registers and the
+//! opcode/trap dispatch tables) and exposes everything needed to embed the
+//! core in other tools: construct a machine, load an object image, then
+//! [`step`](Emulator::step) or [`run`](Emulator::run) it while inspecting
+//! registers and memory.
+//!
+//! Programs can be produced without an external toolchain through
+//! [`assembler::assemble`], and inspected again with
+//! [`disassembler::disassemble`]. Both the executor and the disassembler work
+//! on the typed [`Instruction`] produced by [`decode`].
+//!
+//! ```no_run
+//! use lc3_emu::{Emulator, Register};
+//! use std::fs::File;
+//!
+//! let mut emu = Emulator::new();
+//! emu.load_image(File::open("program.obj").unwrap()).unwrap();
+//! if let Err(err) = emu.run() {
+//!     eprintln!("{}", err);
+//! }
+//! println!("R0 = {:#06x}", emu.register(Register::Rr0));
+//! ```
+
+pub mod assembler;
+pub mod block;
+pub mod console;
+pub mod debugger;
+pub mod decoder;
+pub mod disassembler;
+pub mod emulator;
+pub mod error;
+pub mod gdb;
+pub mod instruction;
+pub mod keyboard;
+pub mod loader;
+pub mod opcode;
+pub mod os;
+pub mod register;
+pub mod symbol;
+pub mod terminal;
+pub mod trace;
+
+pub use console::{BufferConsole, Console, SharedBuffer, StreamConsole};
+pub use decoder::{decode, encode, Instruction, Operand};
+pub use emulator::{
+    Emulator, Engine, Exception, MemoryAccess, Mmu, RunLimits, INTERRUPT_VECTOR_TABLE, MEMORY_MAX,
+    PC_START, SSP_START,
+};
+pub use error::EmulatorError;
+pub use loader::{check_overlaps, Format, Image, LoadError};
+pub use opcode::{Opcode, Opcodes, Trap, TrapMode, Traps};
+pub use register::{ConditionFlag, MemoryMappedRegister, Register, Registers};
+pub use symbol::SymbolTable;
+pub use terminal::TerminalGuard;
+pub use trace::{TraceEntry, Tracer};
@@ -0,0 +1,287 @@
+use crate::emulator::MEMORY_MAX;
+
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Read};
+use std::path::Path;
+use std::str::FromStr;
+
+/// The encodings an image can be stored in.
+///
+/// Every format holds a sequence of 16-bit words. In the object and text
+/// formats the first word is the origin and the rest are loaded from there;
+/// raw images are nothing but data and are loaded at an origin given
+/// alongside them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Big-endian binary, as written by `lc3as` and `lc3_emu asm`.
+    Object,
+    /// ASCII hex, one word per line.
+    Hex,
+    /// ASCII binary, sixteen `0`/`1` characters per line.
+    Bin,
+    /// Little-endian binary with no origin word.
+    RawLe,
+    /// Big-endian binary with no origin word.
+    RawBe,
+}
+
+impl Format {
+    /// Picks the format from a file extension: `.hex` and `.bin` are the
+    /// text formats, anything else is an object file.
+    pub fn from_path(path: &Path) -> Format {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("hex") => Format::Hex,
+            Some(ext) if ext.eq_ignore_ascii_case("bin") => Format::Bin,
+            _ => Format::Object,
+        }
+    }
+
+    /// Returns `true` if images in this format start with their origin.
+    pub fn has_origin(self) -> bool {
+        !matches!(self, Format::RawLe | Format::RawBe)
+    }
+}
+
+impl FromStr for Format {
+    type Err = ();
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "obj" => Ok(Format::Object),
+            "hex" => Ok(Format::Hex),
+            "bin" => Ok(Format::Bin),
+            "raw-le" => Ok(Format::RawLe),
+            "raw-be" => Ok(Format::RawBe),
+            _ => Err(()),
+        }
+    }
+}
+
+/// A block of words and the address it is loaded at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub origin: u16,
+    pub words: Vec<u16>,
+}
+
+/// Why an image could not be loaded.
+#[derive(Debug)]
+pub enum LoadError {
+    /// Reading the image failed.
+    Io(io::Error),
+    /// The image is too short to hold its origin word.
+    Truncated,
+    /// A raw image was given no origin to load at.
+    MissingOrigin,
+    /// An origin was given for an image that starts with its own.
+    UnexpectedOrigin,
+    /// The image ends half way through a word.
+    OddLength(usize),
+    /// A line of a text image does not hold a word.
+    Syntax { line: usize, text: String },
+    /// The image runs past the end of memory.
+    OutOfBounds { origin: u16, words: usize },
+    /// Two images, given by their positions in the load order, both claim
+    /// the memory starting at `address`.
+    Overlap {
+        first: usize,
+        second: usize,
+        address: u16,
+    },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Io(err) => write!(f, "{}", err),
+            LoadError::Truncated => write!(f, "image is truncated: missing origin"),
+            LoadError::MissingOrigin => write!(f, "raw image needs an origin to load at"),
+            LoadError::UnexpectedOrigin => {
+                write!(f, "image has its own origin; only raw images take one")
+            }
+            LoadError::OddLength(len) => {
+                write!(f, "image has an odd length of {} bytes", len)
+            }
+            LoadError::Syntax { line, text } => {
+                write!(f, "line {}: '{}' is not a word", line, text)
+            }
+            LoadError::OutOfBounds { origin, words } => write!(
+                f,
+                "{} words at x{:04X} run past the end of memory",
+                words, origin
+            ),
+            LoadError::Overlap {
+                first,
+                second,
+                address,
+            } => write!(
+                f,
+                "images {} and {} overlap at x{:04X}",
+                first + 1,
+                second + 1,
+                address
+            ),
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LoadError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for LoadError {
+    fn from(err: io::Error) -> Self {
+        LoadError::Io(err)
+    }
+}
+
+impl Image {
+    /// Checks that `words` fit in memory when loaded at `origin`.
+    pub fn new(origin: u16, words: Vec<u16>) -> Result<Self, LoadError> {
+        if origin as usize + words.len() > MEMORY_MAX {
+            return Err(LoadError::OutOfBounds {
+                origin,
+                words: words.len(),
+            });
+        }
+        Ok(Image { origin, words })
+    }
+
+    /// Parses a big-endian `.obj` image: the origin word followed by the
+    /// words to load there.
+    pub fn from_object_bytes(bytes: &[u8]) -> Result<Self, LoadError> {
+        Image::parse(bytes, Format::Object, None)
+    }
+
+    /// Reads a whole `.obj` image from `reader`.
+    pub fn read_object<R: Read>(reader: R) -> Result<Self, LoadError> {
+        Image::read(reader, Format::Object, None)
+    }
+
+    /// Reads a whole image in `format` from `reader`.
+    pub fn read<R: Read>(
+        mut reader: R,
+        format: Format,
+        origin: Option<u16>,
+    ) -> Result<Self, LoadError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        Image::parse(&bytes, format, origin)
+    }
+
+    /// Decodes `bytes` in `format`. Raw images are loaded at `origin`,
+    /// which they require; every other format takes its origin from its
+    /// first word and rejects an explicit one.
+    pub fn parse(bytes: &[u8], format: Format, origin: Option<u16>) -> Result<Self, LoadError> {
+        match (format.has_origin(), origin) {
+            (true, Some(_)) => return Err(LoadError::UnexpectedOrigin),
+            (false, None) => return Err(LoadError::MissingOrigin),
+            _ => {}
+        }
+        if format == Format::Object && bytes.len() < 2 {
+            return Err(LoadError::Truncated);
+        }
+
+        let mut words = match format {
+            Format::Object | Format::RawBe => binary_words(bytes, u16::from_be_bytes)?,
+            Format::RawLe => binary_words(bytes, u16::from_le_bytes)?,
+            Format::Hex => text_words(bytes, parse_hex_word)?,
+            Format::Bin => text_words(bytes, parse_bin_word)?,
+        };
+
+        let origin = match origin {
+            Some(origin) => origin,
+            None if words.is_empty() => return Err(LoadError::Truncated),
+            None => words.remove(0),
+        };
+        Image::new(origin, words)
+    }
+
+    /// One past the last address the image occupies.
+    pub fn end(&self) -> usize {
+        self.origin as usize + self.words.len()
+    }
+
+    /// Returns the first address both images occupy.
+    pub fn overlap(&self, other: &Image) -> Option<u16> {
+        let start = self.origin.max(other.origin);
+        if (start as usize) < self.end().min(other.end()) {
+            Some(start)
+        } else {
+            None
+        }
+    }
+}
+
+/// Checks that no two of `images` share an address.
+pub fn check_overlaps(images: &[Image]) -> Result<(), LoadError> {
+    for (first, a) in images.iter().enumerate() {
+        for (second, b) in images.iter().enumerate().skip(first + 1) {
+            if let Some(address) = a.overlap(b) {
+                return Err(LoadError::Overlap {
+                    first,
+                    second,
+                    address,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+fn binary_words(bytes: &[u8], decode: fn([u8; 2]) -> u16) -> Result<Vec<u16>, LoadError> {
+    if !bytes.len().is_multiple_of(2) {
+        return Err(LoadError::OddLength(bytes.len()));
+    }
+    Ok(bytes
+        .chunks_exact(2)
+        .map(|pair| decode([pair[0], pair[1]]))
+        .collect())
+}
+
+/// Decodes one word per line, skipping blank lines and `;` comments.
+fn text_words(bytes: &[u8], parse: fn(&str) -> Option<u16>) -> Result<Vec<u16>, LoadError> {
+    let text = String::from_utf8_lossy(bytes);
+    let mut words = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let word = line.split(';').next().unwrap_or("").trim();
+        if word.is_empty() {
+            continue;
+        }
+        match parse(word) {
+            Some(value) => words.push(value),
+            None => {
+                return Err(LoadError::Syntax {
+                    line: number + 1,
+                    text: word.to_string(),
+                })
+            }
+        }
+    }
+    Ok(words)
+}
+
+fn parse_hex_word(text: &str) -> Option<u16> {
+    let digits = text
+        .strip_prefix("0x")
+        .or_else(|| text.strip_prefix('x'))
+        .or_else(|| text.strip_prefix('X'))
+        .unwrap_or(text);
+    if digits.is_empty() || digits.len() > 4 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    u16::from_str_radix(digits, 16).ok()
+}
+
+fn parse_bin_word(text: &str) -> Option<u16> {
+    if text.len() != 16 || !text.chars().all(|c| c == '0' || c == '1') {
+        return None;
+    }
+    u16::from_str_radix(text, 2).ok()
+}
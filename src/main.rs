@@ -1,75 +1,431 @@
-mod emulator;
-mod instruction;
-mod opcode;
-mod register;
-use emulator::*;
-use instruction::*;
-use opcode::*;
-use register::*;
-
-use std::{convert::TryFrom, env, fs::File, path::PathBuf, process};
-use termios::*;
+use lc3_emu::assembler::{assemble, parse_number};
+use lc3_emu::debugger::Debugger;
+use lc3_emu::disassembler::disassemble_words;
+use lc3_emu::gdb;
+use lc3_emu::instruction::help;
+use lc3_emu::{
+    Emulator, EmulatorError, Engine, Format, Image, LoadError, Register, RunLimits, StreamConsole,
+    SymbolTable, TerminalGuard, Tracer,
+};
+
+use std::io::{BufWriter, Read, Write};
+use std::time::Duration;
+use std::{env, fs, fs::File, io, path::PathBuf, process};
 
 fn main() {
-    let stdin = 0;
-    let termios = termios::Termios::from_fd(stdin).unwrap();
+    let args: Vec<String> = env::args().collect();
 
-    // make a mutable copy of termios
-    // that we will modify
-    let mut new_termios = termios.clone();
-    new_termios.c_iflag &= IGNBRK | BRKINT | PARMRK | ISTRIP | INLCR | IGNCR | ICRNL | IXON;
-    new_termios.c_lflag &= !(ICANON | ECHO); // no echo and canonical mode
+    if args.get(1).map(String::as_str) == Some("asm") {
+        process::exit(asm(&args[2..]));
+    }
+    if args.get(1).map(String::as_str) == Some("disasm") {
+        process::exit(disasm(&args[2..]));
+    }
 
-    tcsetattr(stdin, TCSANOW, &mut new_termios).unwrap();
+    let mut debug = false;
+    let mut os = false;
+    let mut headless = false;
+    let mut input: Option<PathBuf> = None;
+    let mut output: Option<PathBuf> = None;
+    let mut gdb: Option<String> = None;
+    let mut entry: Option<u16> = None;
+    let mut format: Option<Format> = None;
+    let mut origin: Option<u16> = None;
+    let mut binaries: Vec<PathBuf> = Vec::new();
+    let mut symbol_files: Vec<PathBuf> = Vec::new();
+    let mut limits = RunLimits::default();
+    let mut trace: Option<PathBuf> = None;
+    let mut trace_range: Option<(u16, u16)> = None;
+    let mut trace_limit: Option<usize> = None;
+    let mut engine = Engine::default();
 
-    let args: Vec<String> = env::args().collect();
-    let mut emu: Emulator;
-
-    match args.len() {
-        2 => {
-            let binary: PathBuf = PathBuf::from(args[1].parse::<String>().unwrap());
-            println!("Emulation target is: '{}'", binary.display());
+    // accept --option=value as well as --option value
+    let args: Vec<String> = args[1..]
+        .iter()
+        .flat_map(|arg| match arg.split_once('=') {
+            Some((flag, value)) if VALUE_OPTIONS.contains(&flag) => {
+                vec![flag.to_string(), value.to_string()]
+            }
+            _ => vec![arg.clone()],
+        })
+        .collect();
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--debug" => debug = true,
+            "--os" => os = true,
+            "--headless" => headless = true,
+            "--input" => match rest.next() {
+                Some(path) => input = Some(PathBuf::from(path)),
+                None => usage("--input requires a file"),
+            },
+            "--output" => match rest.next() {
+                Some(path) => output = Some(PathBuf::from(path)),
+                None => usage("--output requires a file"),
+            },
+            "--gdb" => match rest.next() {
+                Some(address) => gdb = Some(address.clone()),
+                None => usage("--gdb requires an address"),
+            },
+            "--entry" => match rest.next().and_then(|text| parse_number(text)) {
+                Some(address) if (0..=0xFFFF).contains(&address) => entry = Some(address as u16),
+                _ => usage("--entry requires an address"),
+            },
+            "--symbols" => match rest.next() {
+                Some(path) => symbol_files.push(PathBuf::from(path)),
+                None => usage("--symbols requires a file"),
+            },
+            "--max-instructions" => match rest.next().and_then(|text| text.parse().ok()) {
+                Some(limit) => limits.max_instructions = Some(limit),
+                None => usage("--max-instructions requires a number"),
+            },
+            "--timeout" => match rest
+                .next()
+                .and_then(|text| text.parse().ok())
+                .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
             {
-                let file = File::open(&binary).unwrap_or_else(|err| {
-                    panic!("Could not open file '{}': {}", binary.display(), err)
-                });
+                Some(timeout) => limits.timeout = Some(timeout),
+                None => usage("--timeout requires a number of seconds"),
+            },
+            "--trace" => match rest.next() {
+                Some(path) => trace = Some(PathBuf::from(path)),
+                None => usage("--trace requires a file"),
+            },
+            "--trace-range" => match rest.next().and_then(|text| parse_range(text)) {
+                Some(range) => trace_range = Some(range),
+                None => usage("--trace-range requires <start>:<end>"),
+            },
+            "--trace-limit" => match rest.next().and_then(|text| text.parse().ok()) {
+                Some(limit) => trace_limit = Some(limit),
+                None => usage("--trace-limit requires a number of entries"),
+            },
+            "--format" => match rest.next().and_then(|name| name.parse().ok()) {
+                Some(name) => format = Some(name),
+                None => usage("--format requires one of obj, hex, bin, raw-le, raw-be"),
+            },
+            "--origin" => match rest.next().and_then(|text| parse_number(text)) {
+                Some(address) if (0..=0xFFFF).contains(&address) => origin = Some(address as u16),
+                _ => usage("--origin requires an address"),
+            },
+            "--engine" => match rest.next().and_then(|name| name.parse().ok()) {
+                Some(name) => engine = name,
+                None => usage("--engine requires interpreter or block"),
+            },
+            _ if !arg.starts_with("--") => binaries.push(PathBuf::from(arg)),
+            _ => usage(&format!("Invalid argument '{}'", arg)),
+        }
+    }
 
-                emu = Emulator::new();
+    if binaries.is_empty() {
+        usage("No binary given");
+    }
+    if origin.is_some() && binaries.len() > 1 {
+        usage("--origin applies to a single binary");
+    }
 
-                read_image_file(file, &mut emu);
+    let images: Vec<Image> = binaries
+        .iter()
+        .map(|binary| {
+            let format = format.unwrap_or_else(|| Format::from_path(binary));
+            match (format.has_origin(), origin) {
+                (true, Some(_)) => usage("--origin only applies to raw-le and raw-be images"),
+                (false, None) => usage("raw-le and raw-be images require --origin"),
+                _ => {}
             }
+            File::open(binary)
+                .map_err(LoadError::Io)
+                .and_then(|file| Image::read(file, format, origin))
+                .unwrap_or_else(|err| fail(&format!("{}: {}", binary.display(), err)))
+        })
+        .collect();
+
+    let mut emu = Emulator::new();
+    emu.engine = engine;
+    if os {
+        lc3_emu::os::install(&mut emu);
+    }
+    if let Err(err) = emu.load_images(&images, entry) {
+        match err {
+            EmulatorError::Load(LoadError::Overlap {
+                first,
+                second,
+                address,
+            }) => fail(&format!(
+                "'{}' and '{}' overlap at x{:04X}",
+                binaries[first].display(),
+                binaries[second].display(),
+                address
+            )),
+            err => fail(&err.to_string()),
+        }
+    }
+
+    for path in &symbol_files {
+        match fs::read_to_string(path) {
+            Ok(text) => emu.symbols.extend(&SymbolTable::from_sym_str(&text)),
+            Err(err) => fail(&format!("Could not read '{}': {}", path.display(), err)),
+        }
+    }
+    // pick up the table the assembler writes next to each object file
+    for binary in &binaries {
+        if let Ok(text) = fs::read_to_string(binary.with_extension("sym")) {
+            emu.symbols.extend(&SymbolTable::from_sym_str(&text));
+        }
+    }
+
+    if let Some(path) = &trace {
+        let file = File::create(path).unwrap_or_else(|err| {
+            fail(&format!(
+                "Could not create file '{}': {}",
+                path.display(),
+                err
+            ))
+        });
+        let mut tracer = Tracer::new(BufWriter::new(file));
+        if let Some((start, end)) = trace_range {
+            tracer = tracer.with_range(start..=end);
+        }
+        if let Some(limit) = trace_limit {
+            tracer = tracer.with_limit(limit);
+        }
+        emu.tracer = Some(tracer);
+    }
+
+    if headless || debug || input.is_some() || output.is_some() {
+        let reader: Box<dyn Read + Send> = match &input {
+            Some(path) => Box::new(File::open(path).unwrap_or_else(|err| {
+                fail(&format!(
+                    "Could not open file '{}': {}",
+                    path.display(),
+                    err
+                ))
+            })),
+            // the debugger reads its commands from stdin, so a program
+            // being debugged only gets input from --input
+            None if debug => Box::new(io::empty()),
+            None => Box::new(io::stdin()),
+        };
+        let writer: Box<dyn Write + Send> = match &output {
+            Some(path) => Box::new(File::create(path).unwrap_or_else(|err| {
+                fail(&format!(
+                    "Could not create file '{}': {}",
+                    path.display(),
+                    err
+                ))
+            })),
+            None => Box::new(io::stdout()),
+        };
+        emu.set_console(StreamConsole::new(reader, writer));
+    }
+
+    // headless runs never touch the terminal, so they work under pipes,
+    // CI and cron; the debugger reads its commands line by line, so it
+    // leaves the terminal in canonical mode too
+    let terminal = if headless || debug {
+        TerminalGuard::disabled()
+    } else {
+        TerminalGuard::raw()
+            .unwrap_or_else(|err| fail(&format!("Could not set up the terminal: {}", err)))
+    };
+
+    if !headless {
+        for binary in &binaries {
+            println!("Emulation target is: '{}'", binary.display());
+        }
+    }
+
+    let mut result = Ok(());
+    if debug {
+        let mut debugger = Debugger::new(io::stdin().lock(), io::stdout());
+        if let Err(err) = debugger.run(&mut emu) {
+            eprintln!("Debugger failed: {}", err);
+        }
+    } else if let Some(address) = gdb {
+        eprintln!("Waiting for GDB on {}", address);
+        if let Err(err) = gdb::listen(address.as_str(), &mut emu) {
+            eprintln!("GDB session failed: {}", err);
+        }
+    } else {
+        result = emu.run_limited(limits);
+    }
+
+    // process::exit skips destructors, so restore the terminal first
+    drop(terminal);
+
+    match result {
+        Ok(()) => process::exit(0),
+        Err(err @ (EmulatorError::InstructionLimit { .. } | EmulatorError::Timeout { .. })) => {
+            eprintln!("{}", diagnostic(&emu, &err));
+            eprint!("{}", register_report(&emu));
+            process::exit(LIMIT_EXIT_STATUS);
         }
+        Err(err) => fail(&diagnostic(&emu, &err)),
+    }
+}
+
+/// Exit status used when `--max-instructions` or `--timeout` stops the
+/// program, distinct from a fault (1) and a usage error (2).
+const LIMIT_EXIT_STATUS: i32 = 3;
+
+/// The options that take a value, and so may be written `--option=value`.
+const VALUE_OPTIONS: [&str; 13] = [
+    "--input",
+    "--output",
+    "--gdb",
+    "--entry",
+    "--symbols",
+    "--max-instructions",
+    "--timeout",
+    "--trace",
+    "--trace-range",
+    "--trace-limit",
+    "--format",
+    "--origin",
+    "--engine",
+];
+
+/// Lists the PC, PSR and general purpose registers.
+fn register_report(emu: &Emulator) -> String {
+    let mut report = format!(
+        "PC  x{:04X} ({})  PSR x{:04X}\n",
+        emu.register(Register::Rpc),
+        emu.describe_address(emu.register(Register::Rpc)),
+        emu.register(Register::Rpsr)
+    );
+    for r in 0..8 {
+        report.push_str(&format!(
+            "R{}  x{:04X}{}",
+            r,
+            emu.register(Register::general(r)),
+            if r % 4 == 3 { "\n" } else { "  " }
+        ));
+    }
+    report
+}
+
+/// Describes a fault along with the instruction that raised it, naming
+/// the address after the nearest label when symbols are loaded.
+fn diagnostic(emu: &Emulator, err: &EmulatorError) -> String {
+    match (err.pc(), err.instruction()) {
+        (Some(pc), Some(instruction)) => format!(
+            "{} at {}, instruction x{:04X}",
+            err,
+            emu.describe_address(pc),
+            instruction
+        ),
+        (Some(pc), None) => format!("{} at {}", err, emu.describe_address(pc)),
+        _ => err.to_string(),
+    }
+}
+
+/// Parses an inclusive address range written as `start:end`.
+fn parse_range(text: &str) -> Option<(u16, u16)> {
+    let (start, end) = text.split_once(':')?;
+    let address = |text: &str| {
+        parse_number(text)
+            .filter(|n| (0..=0xFFFF).contains(n))
+            .map(|n| n as u16)
+    };
+    let (start, end) = (address(start)?, address(end)?);
+    (start <= end).then_some((start, end))
+}
+
+/// Prints the usage and `message`, then exits with status 2.
+fn usage(message: &str) -> ! {
+    help();
+    eprintln!("{}", message);
+    process::exit(2);
+}
+
+/// Prints `message` and exits with status 1.
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
+}
+
+fn asm(args: &[String]) -> i32 {
+    let (source, output) = match args {
+        [source] => (
+            PathBuf::from(source),
+            PathBuf::from(source).with_extension("obj"),
+        ),
+        [source, flag, output] if flag == "-o" => (PathBuf::from(source), PathBuf::from(output)),
         _ => {
             help();
-            panic!("Invalid arguments");
+            return 2;
+        }
+    };
+
+    let text = match fs::read_to_string(&source) {
+        Ok(text) => text,
+        Err(err) => {
+            eprintln!("Could not read '{}': {}", source.display(), err);
+            return 1;
         }
+    };
+
+    let program = match assemble(&text) {
+        Ok(program) => program,
+        Err(err) => {
+            eprintln!("{}: {}", source.display(), err);
+            return 1;
+        }
+    };
+
+    let symbols = output.with_extension("sym");
+    if let Err(err) = fs::write(&output, program.to_object_bytes())
+        .and_then(|_| fs::write(&symbols, program.symbols.to_sym_string()))
+    {
+        eprintln!("Could not write '{}': {}", output.display(), err);
+        return 1;
     }
 
-    emu.registers.update(
-        Register::Rcond,
-        ConditionFlag::get_cflag_value(ConditionFlag::FlZro),
+    println!(
+        "Assembled {} words at x{:04X} into '{}'",
+        program.words.len(),
+        program.origin,
+        output.display()
     );
-    emu.registers.update(Register::Rpc, PC_START);
+    0
+}
 
-    let mut instr: u16;
-    let mut op: Result<Opcode, ()>;
+fn disasm(args: &[String]) -> i32 {
+    let (object, symbols) = match args {
+        [object] => (
+            PathBuf::from(object),
+            PathBuf::from(object).with_extension("sym"),
+        ),
+        [object, flag, symbols] if flag == "--symbols" => {
+            (PathBuf::from(object), PathBuf::from(symbols))
+        }
+        _ => {
+            help();
+            return 2;
+        }
+    };
 
-    while emu.running {
-        instr = emu
-            .memory
-            .read(emu.registers.get_value(Register::Rpc) as usize);
-        emu.registers
-            .update(Register::Rpc, emu.registers.get_value(Register::Rpc) + 1);
-        op = Opcode::try_from(instr >> 12);
+    let bytes = match fs::read(&object) {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            eprintln!("Could not read '{}': {}", object.display(), err);
+            return 1;
+        }
+    };
 
-        if let Ok(op) = op {
-            emu.opcodes.clone().call(op, &mut emu, instr);
-        } else {
-            eprintln!("Invalid instruction");
-            emu.running = false;
+    let image = match Image::from_object_bytes(&bytes) {
+        Ok(image) => image,
+        Err(err) => {
+            eprintln!("{}: {}", object.display(), err);
+            return 1;
         }
-    }
+    };
 
-    tcsetattr(stdin, TCSANOW, &termios).unwrap();
-    process::exit(1);
+    let symbols = fs::read_to_string(&symbols)
+        .map(|text| SymbolTable::from_sym_str(&text))
+        .unwrap_or_default();
+
+    for line in disassemble_words(image.origin, &image.words, Some(&symbols)) {
+        println!("{}", line);
+    }
+    0
 }
@@ -1,6 +1,9 @@
+use crate::decoder::{Instruction, Operand};
 use crate::emulator::*;
 use crate::instruction::*;
+use crate::register::Register;
 
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug)]
 #[repr(u16)]
 pub enum Opcode {
@@ -48,24 +51,33 @@ impl TryFrom<u16> for Opcode {
     }
 }
 
+/// The routines that execute each kind of [`Instruction`], called with its
+/// decoded operands.
 #[derive(Clone)]
 pub struct Opcodes {
-    op_br: fn(&mut Emulator, u16),
-    op_add: fn(&mut Emulator, u16),
-    op_ld: fn(&mut Emulator, u16),
-    op_st: fn(&mut Emulator, u16),
-    op_jsr: fn(&mut Emulator, u16),
-    op_and: fn(&mut Emulator, u16),
-    op_ldr: fn(&mut Emulator, u16),
-    op_str: fn(&mut Emulator, u16),
-    op_rti: fn(),
-    op_not: fn(&mut Emulator, u16),
-    op_ldi: fn(&mut Emulator, u16),
-    op_sti: fn(&mut Emulator, u16),
-    op_jmp: fn(&mut Emulator, u16),
-    op_res: fn(),
-    op_lea: fn(&mut Emulator, u16),
-    op_trap: fn(&mut Emulator, u16),
+    op_br: fn(&mut Emulator, bool, bool, bool, i16),
+    op_add: fn(&mut Emulator, Register, Register, Operand),
+    op_ld: fn(&mut Emulator, Register, i16),
+    op_st: fn(&mut Emulator, Register, i16),
+    op_jsr: fn(&mut Emulator, i16),
+    op_jsrr: fn(&mut Emulator, Register),
+    op_and: fn(&mut Emulator, Register, Register, Operand),
+    op_ldr: fn(&mut Emulator, Register, Register, i16),
+    op_str: fn(&mut Emulator, Register, Register, i16),
+    op_rti: fn(&mut Emulator),
+    op_not: fn(&mut Emulator, Register, Register),
+    op_ldi: fn(&mut Emulator, Register, i16),
+    op_sti: fn(&mut Emulator, Register, i16),
+    op_jmp: fn(&mut Emulator, Register),
+    op_res: fn(&mut Emulator),
+    op_lea: fn(&mut Emulator, Register, i16),
+    op_trap: fn(&mut Emulator, u8),
+}
+
+impl Default for Opcodes {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Opcodes {
@@ -76,6 +88,7 @@ impl Opcodes {
             op_ld,
             op_st,
             op_jsr,
+            op_jsrr,
             op_and,
             op_ldr,
             op_str,
@@ -90,28 +103,47 @@ impl Opcodes {
         }
     }
 
-    pub fn call(&self, op: Opcode, emu: &mut Emulator, instr: u16) {
-        match op {
-            Opcode::OpBr => (self.op_br)(emu, instr),
-            Opcode::OpAdd => (self.op_add)(emu, instr),
-            Opcode::OpLd => (self.op_ld)(emu, instr),
-            Opcode::OpSt => (self.op_st)(emu, instr),
-            Opcode::OpJsr => (self.op_jsr)(emu, instr),
-            Opcode::OpAnd => (self.op_and)(emu, instr),
-            Opcode::OpLdr => (self.op_ldr)(emu, instr),
-            Opcode::OpStr => (self.op_str)(emu, instr),
-            Opcode::OpRti => (self.op_rti)(),
-            Opcode::OpNot => (self.op_not)(emu, instr),
-            Opcode::OpLdi => (self.op_ldi)(emu, instr),
-            Opcode::OpSti => (self.op_sti)(emu, instr),
-            Opcode::OpJmp => (self.op_jmp)(emu, instr),
-            Opcode::OpRes => (self.op_res)(),
-            Opcode::OpLea => (self.op_lea)(emu, instr),
-            Opcode::OpTrap => (self.op_trap)(emu, instr),
+    /// Executes `instruction` with the routine `emu` has installed for it.
+    ///
+    /// Only the one routine is copied out of the table, so the table itself
+    /// never needs cloning to get around the borrow of `emu`.
+    #[inline]
+    pub fn call(emu: &mut Emulator, instruction: Instruction) {
+        let ops = &emu.opcodes;
+        match instruction {
+            Instruction::Br { n, z, p, offset } => (ops.op_br)(emu, n, z, p, offset),
+            Instruction::Add { dr, sr1, src2 } => (ops.op_add)(emu, dr, sr1, src2),
+            Instruction::Ld { dr, offset } => (ops.op_ld)(emu, dr, offset),
+            Instruction::St { sr, offset } => (ops.op_st)(emu, sr, offset),
+            Instruction::Jsr { offset } => (ops.op_jsr)(emu, offset),
+            Instruction::Jsrr { base } => (ops.op_jsrr)(emu, base),
+            Instruction::And { dr, sr1, src2 } => (ops.op_and)(emu, dr, sr1, src2),
+            Instruction::Ldr { dr, base, offset } => (ops.op_ldr)(emu, dr, base, offset),
+            Instruction::Str { sr, base, offset } => (ops.op_str)(emu, sr, base, offset),
+            Instruction::Rti => (ops.op_rti)(emu),
+            Instruction::Not { dr, sr } => (ops.op_not)(emu, dr, sr),
+            Instruction::Ldi { dr, offset } => (ops.op_ldi)(emu, dr, offset),
+            Instruction::Sti { sr, offset } => (ops.op_sti)(emu, sr, offset),
+            Instruction::Jmp { base } => (ops.op_jmp)(emu, base),
+            Instruction::Reserved => (ops.op_res)(emu),
+            Instruction::Lea { dr, offset } => (ops.op_lea)(emu, dr, offset),
+            Instruction::Trap { vector } => (ops.op_trap)(emu, vector),
         }
     }
 }
 
+/// Selects how TRAP instructions are serviced.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum TrapMode {
+    /// Dispatch straight to the Rust routines in [`Traps`].
+    #[default]
+    Native,
+    /// Save the return address in R7 and jump through the trap vector table
+    /// in memory, running whatever service routines are installed there.
+    Os,
+}
+
+#[allow(clippy::enum_variant_names)]
 #[repr(u8)]
 pub enum Trap {
     TrapGetc = 0x20,
@@ -148,6 +180,12 @@ pub struct Traps {
     trap_halt: fn(&mut Emulator),
 }
 
+impl Default for Traps {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Traps {
     pub fn new() -> Self {
         Traps {
@@ -160,14 +198,16 @@ impl Traps {
         }
     }
 
-    pub fn call(&self, trap: Trap, emu: &mut Emulator) {
+    /// Runs the service routine `emu` has installed for `trap`.
+    pub fn call(emu: &mut Emulator, trap: Trap) {
+        let traps = &emu.traps;
         match trap {
-            Trap::TrapGetc => (self.trap_getc)(emu),
-            Trap::TrapOut => (self.trap_out)(emu),
-            Trap::TrapPuts => (self.trap_puts)(emu),
-            Trap::TrapIn => (self.trap_in)(emu),
-            Trap::TrapPutsp => (self.trap_putsp)(emu),
-            Trap::TrapHalt => (self.trap_halt)(emu),
+            Trap::TrapGetc => (traps.trap_getc)(emu),
+            Trap::TrapOut => (traps.trap_out)(emu),
+            Trap::TrapPuts => (traps.trap_puts)(emu),
+            Trap::TrapIn => (traps.trap_in)(emu),
+            Trap::TrapPutsp => (traps.trap_putsp)(emu),
+            Trap::TrapHalt => (traps.trap_halt)(emu),
         }
     }
 }
@@ -0,0 +1,17 @@
+use crate::assembler::assemble;
+use crate::emulator::Emulator;
+use crate::opcode::TrapMode;
+
+/// LC-3 assembly source of the bundled operating system.
+pub const OS_SOURCE: &str = include_str!("os.asm");
+
+/// Assembles the bundled operating system into low memory and switches the
+/// emulator to [`TrapMode::Os`], so TRAPs run its service routines.
+pub fn install(emu: &mut Emulator) {
+    let os = assemble(OS_SOURCE).expect("bundled OS image must assemble");
+
+    for (address, word) in (os.origin as usize..).zip(os.words) {
+        emu.memory.write(address, word);
+    }
+    emu.trap_mode = TrapMode::Os;
+}
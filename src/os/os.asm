@@ -0,0 +1,150 @@
+; Minimal LC-3 operating system image.
+;
+; Fills the trap vector table (x0000-x00FF) with the standard service
+; routines, which talk to the keyboard, display and machine control
+; registers. Unused trap and interrupt vectors are left as x0000, which the
+; emulator reports as an invalid vector.
+
+        .ORIG x0000
+        .BLKW x20
+        .FILL TRAP_GETC         ; x20
+        .FILL TRAP_OUT          ; x21
+        .FILL TRAP_PUTS         ; x22
+        .FILL TRAP_IN           ; x23
+        .FILL TRAP_PUTSP        ; x24
+        .FILL TRAP_HALT         ; x25
+        .BLKW x1DA              ; x26-xFF and the interrupt vector table
+
+; GETC: read one character from the keyboard into R0, without echo.
+TRAP_GETC
+        LDI R0, OS_KBSR
+        BRzp TRAP_GETC
+        LDI R0, OS_KBDR
+        RET
+
+; OUT: write the character in R0 to the display.
+TRAP_OUT
+        ST R1, OUT_R1
+OUT_WAIT
+        LDI R1, OS_DSR
+        BRzp OUT_WAIT
+        STI R0, OS_DDR
+        LD R1, OUT_R1
+        RET
+
+; PUTS: write the zero-terminated string at R0, one character per word.
+TRAP_PUTS
+        ST R7, PUTS_R7
+        ST R0, PUTS_R0
+        ST R1, PUTS_R1
+        ADD R1, R0, #0
+PUTS_LOOP
+        LDR R0, R1, #0
+        BRz PUTS_DONE
+        OUT
+        ADD R1, R1, #1
+        BRnzp PUTS_LOOP
+PUTS_DONE
+        LD R0, PUTS_R0
+        LD R1, PUTS_R1
+        LD R7, PUTS_R7
+        RET
+
+; IN: prompt for a character, echo it, and return it in R0.
+TRAP_IN
+        ST R7, IN_R7
+        LEA R0, IN_PROMPT
+        PUTS
+        GETC
+        OUT
+        ST R0, IN_R0
+        AND R0, R0, #0
+        ADD R0, R0, #10
+        OUT
+        LD R0, IN_R0
+        LD R7, IN_R7
+        RET
+
+; PUTSP: write the zero-terminated string at R0, two characters per word
+; with the first in bits [7:0].
+TRAP_PUTSP
+        ST R7, PUTSP_R7
+        ST R0, PUTSP_R0
+        ST R1, PUTSP_R1
+        ST R2, PUTSP_R2
+        ST R3, PUTSP_R3
+        ST R4, PUTSP_R4
+        ST R5, PUTSP_R5
+        ADD R1, R0, #0
+PUTSP_LOOP
+        LDR R2, R1, #0
+        BRz PUTSP_DONE
+        LD R3, OS_LOW_BYTE
+        AND R0, R2, R3
+        OUT
+        ; shift the high byte down, one bit at a time
+        AND R0, R0, #0
+        ADD R3, R0, #1
+        LD R4, OS_HIGH_BIT
+PUTSP_BIT
+        AND R5, R2, R4
+        BRz PUTSP_SKIP
+        ADD R0, R0, R3
+PUTSP_SKIP
+        ADD R3, R3, R3
+        ADD R4, R4, R4
+        BRnp PUTSP_BIT
+        ADD R0, R0, #0
+        BRz PUTSP_NEXT
+        OUT
+PUTSP_NEXT
+        ADD R1, R1, #1
+        BRnzp PUTSP_LOOP
+PUTSP_DONE
+        LD R0, PUTSP_R0
+        LD R1, PUTSP_R1
+        LD R2, PUTSP_R2
+        LD R3, PUTSP_R3
+        LD R4, PUTSP_R4
+        LD R5, PUTSP_R5
+        LD R7, PUTSP_R7
+        RET
+
+; HALT: announce the halt and stop the clock.
+TRAP_HALT
+        LEA R0, HALT_MESSAGE
+        PUTS
+        AND R0, R0, #0
+        STI R0, OS_MCR
+        BRnzp TRAP_HALT
+
+; Device registers.
+OS_KBSR         .FILL xFE00
+OS_KBDR         .FILL xFE02
+OS_DSR          .FILL xFE04
+OS_DDR          .FILL xFE06
+OS_MCR          .FILL xFFFE
+
+; Constants.
+OS_LOW_BYTE     .FILL x00FF
+OS_HIGH_BIT     .FILL x0100
+
+; Register save slots.
+OUT_R1          .FILL x0000
+PUTS_R0         .FILL x0000
+PUTS_R1         .FILL x0000
+PUTS_R7         .FILL x0000
+IN_R0           .FILL x0000
+IN_R7           .FILL x0000
+PUTSP_R0        .FILL x0000
+PUTSP_R1        .FILL x0000
+PUTSP_R2        .FILL x0000
+PUTSP_R3        .FILL x0000
+PUTSP_R4        .FILL x0000
+PUTSP_R5        .FILL x0000
+PUTSP_R7        .FILL x0000
+
+IN_PROMPT       .STRINGZ "Enter a character: "
+HALT_MESSAGE    .STRINGZ "HALT\n"
+
+        .END
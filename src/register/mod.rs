@@ -1,3 +1,4 @@
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[repr(u16)]
 pub enum Register {
     Rr0 = 0,
@@ -10,6 +11,9 @@ pub enum Register {
     Rr7,
     Rpc,
     Rcond,
+    Rpsr,
+    RsavedSsp,
+    RsavedUsp,
 }
 
 impl TryFrom<u16> for Register {
@@ -27,72 +31,111 @@ impl TryFrom<u16> for Register {
             x if x == Register::Rr7 as u16 => Ok(Register::Rr7),
             x if x == Register::Rpc as u16 => Ok(Register::Rpc),
             x if x == Register::Rcond as u16 => Ok(Register::Rcond),
+            x if x == Register::Rpsr as u16 => Ok(Register::Rpsr),
+            x if x == Register::RsavedSsp as u16 => Ok(Register::RsavedSsp),
+            x if x == Register::RsavedUsp as u16 => Ok(Register::RsavedUsp),
             _ => Err(()),
         }
     }
 }
 
+impl Register {
+    /// The general purpose register named by the low three bits of `field`,
+    /// as encoded in an instruction's DR, SR and BaseR fields.
+    pub fn general(field: u16) -> Register {
+        match field & 0x7 {
+            0 => Register::Rr0,
+            1 => Register::Rr1,
+            2 => Register::Rr2,
+            3 => Register::Rr3,
+            4 => Register::Rr4,
+            5 => Register::Rr5,
+            6 => Register::Rr6,
+            _ => Register::Rr7,
+        }
+    }
+}
+
+/// Processor Status Register layout: PSR[15] is the privilege bit (set for
+/// user mode), PSR[10:8] the priority level and PSR[2:0] the N, Z and P
+/// condition codes.
+pub const PSR_USER_MODE: u16 = 1 << 15;
+pub const PSR_PRIORITY_MASK: u16 = 0x7 << 8;
+pub const PSR_COND_MASK: u16 = 0x7;
+
+/// The general purpose registers, the PC and the Processor Status Register,
+/// plus the Saved_SSP/Saved_USP shadow registers that hold the inactive
+/// stack pointer while R6 serves the other privilege mode. `Rcond` is a view
+/// of the condition code bits of the PSR.
+///
+/// Writes to R0-R7 are also remembered as a bit mask, so a tracer can tell
+/// which registers an instruction wrote even when the value did not change.
 #[derive(Debug, Copy, Clone)]
 pub struct Registers {
-    r_r0: u16,
-    r_r1: u16,
-    r_r2: u16,
-    r_r3: u16,
-    r_r4: u16,
-    r_r5: u16,
-    r_r6: u16,
-    r_r7: u16,
-    r_pc: u16,
-    r_cond: u16,
+    /// Indexed by `Register as usize`; the `Rcond` slot is unused since the
+    /// condition codes live in the PSR.
+    values: [u16; REGISTER_COUNT],
+    written: u8,
+}
+
+const REGISTER_COUNT: usize = Register::RsavedUsp as usize + 1;
+
+impl Default for Registers {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Registers {
     pub fn new() -> Self {
         Registers {
-            r_r0: 0,
-            r_r1: 0,
-            r_r2: 0,
-            r_r3: 0,
-            r_r4: 0,
-            r_r5: 0,
-            r_r6: 0,
-            r_r7: 0,
-            r_pc: 0,
-            r_cond: 0,
+            values: [0; REGISTER_COUNT],
+            written: 0,
         }
     }
 
+    #[inline]
     pub fn update(&mut self, reg: Register, value: u16) {
         match reg {
-            Register::Rr0 => self.r_r0 = value,
-            Register::Rr1 => self.r_r1 = value,
-            Register::Rr2 => self.r_r2 = value,
-            Register::Rr3 => self.r_r3 = value,
-            Register::Rr4 => self.r_r4 = value,
-            Register::Rr5 => self.r_r5 = value,
-            Register::Rr6 => self.r_r6 = value,
-            Register::Rr7 => self.r_r7 = value,
-            Register::Rpc => self.r_pc = value,
-            Register::Rcond => self.r_cond = value,
+            Register::Rcond => {
+                let psr = &mut self.values[Register::Rpsr as usize];
+                *psr = (*psr & !PSR_COND_MASK) | (value & PSR_COND_MASK);
+            }
+            _ => {
+                if (reg as u16) < 8 {
+                    self.written |= 1 << reg as u16;
+                }
+                self.values[reg as usize] = value;
+            }
         }
     }
 
-    pub fn get_value(self, reg: Register) -> u16 {
+    #[inline]
+    pub fn get_value(&self, reg: Register) -> u16 {
         match reg {
-            Register::Rr0 => self.r_r0,
-            Register::Rr1 => self.r_r1,
-            Register::Rr2 => self.r_r2,
-            Register::Rr3 => self.r_r3,
-            Register::Rr4 => self.r_r4,
-            Register::Rr5 => self.r_r5,
-            Register::Rr6 => self.r_r6,
-            Register::Rr7 => self.r_r7,
-            Register::Rpc => self.r_pc,
-            Register::Rcond => self.r_cond,
+            Register::Rcond => self.values[Register::Rpsr as usize] & PSR_COND_MASK,
+            _ => self.values[reg as usize],
         }
     }
+
+    /// Returns the general purpose registers written since the last call,
+    /// as a mask with bit `n` standing for R`n`, and clears it.
+    pub fn take_written(&mut self) -> u8 {
+        std::mem::take(&mut self.written)
+    }
+
+    /// Returns `true` when the PSR privilege bit selects user mode.
+    pub fn user_mode(&self) -> bool {
+        self.get_value(Register::Rpsr) & PSR_USER_MODE != 0
+    }
+
+    /// Returns the priority level (PL0-PL7) held in the PSR.
+    pub fn priority(&self) -> u16 {
+        (self.get_value(Register::Rpsr) & PSR_PRIORITY_MASK) >> 8
+    }
 }
 
+#[allow(clippy::enum_variant_names)]
 #[repr(u8)]
 pub enum ConditionFlag {
     FlPos,
@@ -110,7 +153,12 @@ impl ConditionFlag {
     }
 }
 
+/// Addresses of the memory-mapped device registers: keyboard status and
+/// data, display status and data, and the machine control register.
 pub enum MemoryMappedRegister {
     Kbsr = 0xFE00,
     Kbdr = 0xFE02,
+    Dsr = 0xFE04,
+    Ddr = 0xFE06,
+    Mcr = 0xFFFE,
 }
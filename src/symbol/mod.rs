@@ -0,0 +1,118 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// How far past a label an address may be and still be named after it.
+const MAX_LABEL_OFFSET: u16 = 0xFF;
+
+/// Mapping between labels and the addresses they name.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SymbolTable {
+    symbols: BTreeMap<String, u16>,
+}
+
+impl SymbolTable {
+    pub fn new() -> Self {
+        SymbolTable {
+            symbols: BTreeMap::new(),
+        }
+    }
+
+    /// Adds `label` at `address`, returning `false` if it was already defined.
+    pub fn insert(&mut self, label: &str, address: u16) -> bool {
+        if self.symbols.contains_key(label) {
+            return false;
+        }
+        self.symbols.insert(label.to_string(), address);
+        true
+    }
+
+    /// Returns the address named by `label`.
+    pub fn address_of(&self, label: &str) -> Option<u16> {
+        self.symbols.get(label).copied()
+    }
+
+    /// Returns a label defined exactly at `address`.
+    pub fn label_at(&self, address: u16) -> Option<&str> {
+        self.symbols
+            .iter()
+            .find(|(_, &a)| a == address)
+            .map(|(label, _)| label.as_str())
+    }
+
+    /// Returns the closest label at or before `address`, and how far past
+    /// it `address` lies.
+    pub fn nearest(&self, address: u16) -> Option<(&str, u16)> {
+        self.symbols
+            .iter()
+            .filter(|(_, &a)| a <= address && address - a <= MAX_LABEL_OFFSET)
+            .min_by_key(|(label, &a)| (address - a, label.as_str()))
+            .map(|(label, &a)| (label.as_str(), address - a))
+    }
+
+    /// Names `address` as `LABEL` or `LABEL+n` after the nearest preceding
+    /// label, falling back to `xNNNN` when no label is close enough.
+    pub fn describe(&self, address: u16) -> String {
+        match self.nearest(address) {
+            Some((label, 0)) => label.to_string(),
+            Some((label, offset)) => format!("{}+{}", label, offset),
+            None => format!("x{:04X}", address),
+        }
+    }
+
+    /// Adds every entry of `other` that does not clash with an existing
+    /// label.
+    pub fn extend(&mut self, other: &SymbolTable) {
+        for (label, address) in other.iter() {
+            self.insert(label, address);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    /// Iterates over `(label, address)` pairs ordered by label.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, u16)> {
+        self.symbols.iter().map(|(label, &a)| (label.as_str(), a))
+    }
+
+    /// Parses the `.sym` format emitted by `lc3as` (and by
+    /// [`to_sym_string`](Self::to_sym_string)). Lines that do not hold a
+    /// `label address` pair are skipped.
+    pub fn from_sym_str(text: &str) -> Self {
+        let mut table = SymbolTable::new();
+        for line in text.lines() {
+            let entry = line.trim_start_matches("//");
+            let mut fields = entry.split_whitespace();
+            if let (Some(label), Some(address), None) =
+                (fields.next(), fields.next(), fields.next())
+            {
+                if let Ok(address) = u16::from_str_radix(address, 16) {
+                    table.insert(label, address);
+                }
+            }
+        }
+        table
+    }
+
+    /// Renders the table in the `.sym` format emitted by `lc3as`.
+    pub fn to_sym_string(&self) -> String {
+        let mut entries: Vec<(&str, u16)> = self.iter().collect();
+        entries.sort_by_key(|&(_, address)| address);
+
+        let mut out = String::new();
+        out.push_str("// Symbol table\n");
+        out.push_str("// Scope level 0:\n");
+        out.push_str("//\tSymbol Name       Page Address\n");
+        out.push_str("//\t----------------  ------------\n");
+        for (label, address) in entries {
+            writeln!(out, "//\t{:<16}  {:04X}", label, address).unwrap();
+        }
+        out.push('\n');
+        out
+    }
+}
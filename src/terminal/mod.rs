@@ -0,0 +1,112 @@
+use std::io;
+use std::panic;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Once, OnceLock};
+
+use termios::*;
+
+const STDIN: i32 = 0;
+
+/// Settings of the terminal before the first guard changed them.
+static ORIGINAL: OnceLock<Termios> = OnceLock::new();
+/// Whether the terminal is currently in raw mode.
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+static INSTALL_HOOKS: Once = Once::new();
+
+/// Keeps the terminal in raw mode for as long as it is alive.
+///
+/// Raw mode turns off echo, line buffering and input translation so the
+/// emulated keyboard sees every key as it is typed. The original settings
+/// come back when the guard is dropped, when the program panics, and when
+/// it is stopped by SIGINT or SIGTERM. Ctrl-C still raises SIGINT.
+pub struct TerminalGuard {
+    enabled: bool,
+}
+
+impl TerminalGuard {
+    /// Puts standard input into raw mode. If standard input is not a
+    /// terminal (a pipe, a file, CI) the guard is disabled and the terminal
+    /// is left alone.
+    pub fn raw() -> io::Result<Self> {
+        if !is_tty() {
+            return Ok(Self::disabled());
+        }
+
+        let original = match ORIGINAL.get() {
+            Some(original) => *original,
+            None => {
+                let current = Termios::from_fd(STDIN)?;
+                *ORIGINAL.get_or_init(|| current)
+            }
+        };
+        INSTALL_HOOKS.call_once(install_hooks);
+
+        let mut raw = original;
+        raw.c_iflag &= !(IGNBRK | BRKINT | PARMRK | ISTRIP | INLCR | IGNCR | ICRNL | IXON);
+        raw.c_lflag &= !(ICANON | ECHO | ECHONL | IEXTEN);
+        raw.c_cc[VMIN] = 1;
+        raw.c_cc[VTIME] = 0;
+
+        ACTIVE.store(true, Ordering::SeqCst);
+        if let Err(err) = tcsetattr(STDIN, TCSANOW, &raw) {
+            ACTIVE.store(false, Ordering::SeqCst);
+            return Err(err);
+        }
+        Ok(TerminalGuard { enabled: true })
+    }
+
+    /// A guard that never touches the terminal.
+    pub fn disabled() -> Self {
+        TerminalGuard { enabled: false }
+    }
+
+    /// Returns `true` if this guard put the terminal into raw mode.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        if self.enabled {
+            restore();
+        }
+    }
+}
+
+/// Returns `true` if standard input is attached to a terminal.
+pub fn is_tty() -> bool {
+    unsafe { libc::isatty(STDIN) == 1 }
+}
+
+/// Puts the original terminal settings back, if raw mode is active.
+fn restore() {
+    if ACTIVE.swap(false, Ordering::SeqCst) {
+        if let Some(original) = ORIGINAL.get() {
+            let _ = tcsetattr(STDIN, TCSANOW, original);
+        }
+    }
+}
+
+fn install_hooks() {
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        restore();
+        previous(info);
+    }));
+
+    let handler = on_signal as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    unsafe {
+        libc::signal(libc::SIGINT, handler);
+        libc::signal(libc::SIGTERM, handler);
+    }
+}
+
+extern "C" fn on_signal(signal: libc::c_int) {
+    // only the atomic swap and tcsetattr run here, both safe in a handler
+    restore();
+    unsafe {
+        libc::signal(signal, libc::SIG_DFL);
+        libc::raise(signal);
+    }
+}
@@ -0,0 +1,107 @@
+use crate::disassembler::listing_line;
+use crate::emulator::MemoryAccess;
+use crate::symbol::SymbolTable;
+
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::ops::RangeInclusive;
+
+/// What one executed instruction did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceEntry {
+    pub pc: u16,
+    pub instruction: u16,
+    /// General purpose registers written, with their new values.
+    pub registers: Vec<(u16, u16)>,
+    /// Data reads and writes, in the order they happened.
+    pub accesses: Vec<MemoryAccess>,
+    /// The N, Z and P condition codes after the instruction.
+    pub nzp: u16,
+}
+
+impl TraceEntry {
+    /// Renders the entry as one line: the listing of the instruction
+    /// followed by its effects, e.g.
+    /// `x3001  x1021  LOOP         ADD R0, R0, #1   R0=x0001 NZP=P`.
+    pub fn format(&self, symbols: Option<&SymbolTable>) -> String {
+        let mut line = format!("{:<48}", listing_line(self.pc, self.instruction, symbols));
+        for &(reg, value) in &self.registers {
+            write!(line, " R{}=x{:04X}", reg, value).unwrap();
+        }
+        for access in &self.accesses {
+            match *access {
+                MemoryAccess::Read { address, value } => {
+                    write!(line, " read x{:04X}=x{:04X}", address, value).unwrap()
+                }
+                MemoryAccess::Write { address, value } => {
+                    write!(line, " write x{:04X}=x{:04X}", address, value).unwrap()
+                }
+            }
+        }
+        line.push_str(" NZP=");
+        for (bit, flag) in [(0x4, 'N'), (0x2, 'Z'), (0x1, 'P')] {
+            if self.nzp & bit != 0 {
+                line.push(flag);
+            }
+        }
+        line
+    }
+}
+
+/// Writes a [`TraceEntry`] for every instruction the emulator executes.
+///
+/// Entries can be restricted to instructions whose PC falls in a range, and
+/// capped at a number of entries, after which a final note is written and
+/// tracing stops.
+pub struct Tracer {
+    output: Box<dyn Write + Send>,
+    range: Option<RangeInclusive<u16>>,
+    limit: Option<usize>,
+    written: usize,
+}
+
+impl Tracer {
+    pub fn new<W: Write + Send + 'static>(output: W) -> Self {
+        Tracer {
+            output: Box::new(output),
+            range: None,
+            limit: None,
+            written: 0,
+        }
+    }
+
+    /// Only traces instructions at addresses in `range`.
+    pub fn with_range(mut self, range: RangeInclusive<u16>) -> Self {
+        self.range = Some(range);
+        self
+    }
+
+    /// Stops tracing after `limit` entries.
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Returns `true` while the tracer still wants entries.
+    pub fn is_active(&self) -> bool {
+        self.limit.is_none_or(|limit| self.written < limit)
+    }
+
+    /// Writes `entry` unless it is filtered out.
+    pub fn record(&mut self, entry: &TraceEntry, symbols: Option<&SymbolTable>) -> io::Result<()> {
+        if !self.is_active() || self.range.as_ref().is_some_and(|r| !r.contains(&entry.pc)) {
+            return Ok(());
+        }
+
+        writeln!(self.output, "{}", entry.format(symbols))?;
+        self.written += 1;
+        if !self.is_active() {
+            writeln!(
+                self.output,
+                "Trace limit of {} entries reached",
+                self.written
+            )?;
+        }
+        self.output.flush()
+    }
+}
@@ -0,0 +1,301 @@
+use lc3_emu::assembler::{assemble, parse_number, AsmError};
+
+fn words(source: &str) -> Vec<u16> {
+    assemble(source).unwrap().words
+}
+
+/// Assembles `body` at x3000 and returns the error.
+fn error(body: &str) -> AsmError {
+    assemble(&format!(".ORIG x3000\n{}\n.END\n", body)).unwrap_err()
+}
+
+#[test]
+fn assembles_every_directive() {
+    let program = assemble(
+        r#"
+        ; a comment line
+        .ORIG x4000
+START   .FILL x1234
+        .FILL #-1
+        .FILL TEXT
+        .BLKW 3
+TEXT    .STRINGZ "hi\n"
+        .END
+        .FILL xFFFF
+    "#,
+    )
+    .unwrap();
+
+    assert_eq!(program.origin, 0x4000);
+    assert_eq!(
+        program.words,
+        [0x1234, 0xFFFF, 0x4006, 0, 0, 0, 0x68, 0x69, 0x0A, 0]
+    );
+    assert_eq!(program.symbols.address_of("START"), Some(0x4000));
+    assert_eq!(program.symbols.address_of("TEXT"), Some(0x4006));
+    assert_eq!(program.symbols.len(), 2);
+    assert_eq!(program.to_object_bytes()[..4], [0x40, 0x00, 0x12, 0x34]);
+}
+
+#[test]
+fn stringz_escapes_become_single_words() {
+    assert_eq!(
+        words(
+            r#".ORIG x3000
+        .STRINGZ "\t\"\\\0\e"
+        .END"#
+        ),
+        [0x09, 0x22, 0x5C, 0x00, 0x1B, 0x00]
+    );
+}
+
+#[test]
+fn assembles_every_operate_form() {
+    assert_eq!(
+        words(
+            "
+        .ORIG x3000
+        ADD R1, R2, R3
+        ADD R1, R2, #-16
+        add r7, r0, x0F
+        AND R4, R5, R6
+        AND R4, R5, #15
+        NOT R0, R7
+        .END
+    "
+        ),
+        [0x1283, 0x12B0, 0x1E2F, 0x5946, 0x596F, 0x91FF]
+    );
+}
+
+#[test]
+fn assembles_every_control_form() {
+    assert_eq!(
+        words(
+            "
+        .ORIG x3000
+TOP     BR TOP
+        BRn TOP
+        BRz TOP
+        BRp TOP
+        BRnz TOP
+        BRnp TOP
+        BRzp TOP
+        BRnzp TOP
+        BRp #5
+        JMP R2
+        RET
+        JSR TOP
+        JSR #-1
+        JSRR R3
+        RTI
+        .END
+    "
+        ),
+        [
+            0x0FFF, 0x09FE, 0x05FD, 0x03FC, 0x0DFB, 0x0BFA, 0x07F9, 0x0FF8, 0x0205, 0xC080, 0xC1C0,
+            0x4FF4, 0x4FFF, 0x40C0, 0x8000,
+        ]
+    );
+}
+
+#[test]
+fn assembles_every_memory_form() {
+    assert_eq!(
+        words(
+            "
+        .ORIG x3000
+        LD R0, DATA
+        LDI R1, DATA
+        LEA R2, DATA
+        ST R3, DATA
+        STI R4, DATA
+        LD R5, #-3
+        LDR R6, R7, #-32
+        STR R0, R1, #31
+DATA    .FILL 0
+        .END
+    "
+        ),
+        [0x2007, 0xA206, 0xE405, 0x3604, 0xB803, 0x2BFD, 0x6DE0, 0x705F, 0]
+    );
+}
+
+#[test]
+fn assembles_traps_and_their_aliases() {
+    assert_eq!(
+        words(
+            "
+        .ORIG x3000
+        TRAP x20
+        GETC
+        OUT
+        PUTS
+        IN
+        PUTSP
+        HALT
+        TRAP xFF
+        .END
+    "
+        ),
+        [0xF020, 0xF020, 0xF021, 0xF022, 0xF023, 0xF024, 0xF025, 0xF0FF]
+    );
+}
+
+#[test]
+fn labels_may_stand_alone_or_end_in_a_colon() {
+    let program = assemble(
+        "
+        .ORIG x3000
+FIRST:
+SECOND  ADD R0, R0, #1
+        BRnzp FIRST
+        .END
+    ",
+    )
+    .unwrap();
+    assert_eq!(program.words, [0x1021, 0x0FFE]);
+    assert_eq!(program.symbols.address_of("FIRST"), Some(0x3000));
+    assert_eq!(program.symbols.address_of("SECOND"), Some(0x3000));
+}
+
+#[test]
+fn parses_number_literals() {
+    assert_eq!(parse_number("#10"), Some(10));
+    assert_eq!(parse_number("#-10"), Some(-10));
+    assert_eq!(parse_number("x3000"), Some(0x3000));
+    assert_eq!(parse_number("0x3000"), Some(0x3000));
+    assert_eq!(parse_number("X-1"), Some(-1));
+    assert_eq!(parse_number("b101"), Some(5));
+    assert_eq!(parse_number("42"), Some(42));
+    assert_eq!(parse_number("LOOP"), None);
+    assert_eq!(parse_number("#"), None);
+}
+
+#[test]
+fn rejects_out_of_range_operands() {
+    let cases = [
+        "ADD R0, R0, #16",
+        "AND R0, R0, #-17",
+        "LDR R0, R1, #32",
+        "STR R0, R1, #-33",
+        "LD R0, #256",
+        "BRz #-257",
+        "JSR #1024",
+        "TRAP x100",
+        ".FILL x10000",
+        ".FILL #-32769",
+    ];
+    for case in cases {
+        let err = error(case);
+        assert_eq!(err.line, 2, "{}", case);
+    }
+    assert_eq!(
+        error("ADD R0, R0, #16").message,
+        "value 16 does not fit in 5 bits"
+    );
+    assert_eq!(error("TRAP x100").message, "trap vector out of range");
+}
+
+#[test]
+fn rejects_label_offsets_out_of_range() {
+    let err = assemble(
+        "
+        .ORIG x3000
+        BRnzp FAR
+        .BLKW 256
+FAR     HALT
+        .END
+    ",
+    )
+    .unwrap_err();
+    assert_eq!(err.line, 3);
+    assert_eq!(err.message, "value 256 does not fit in 9 bits");
+
+    // the same distance backwards is still in range
+    let program = assemble(
+        "
+        .ORIG x3000
+NEAR    .BLKW 255
+        BRnzp NEAR
+        .END
+    ",
+    )
+    .unwrap();
+    assert_eq!(program.words[255], 0x0F00);
+}
+
+#[test]
+fn rejects_undefined_and_duplicate_labels() {
+    assert_eq!(error("LD R0, NOWHERE").message, "undefined label 'NOWHERE'");
+    assert_eq!(error(".FILL NOWHERE").message, "undefined label 'NOWHERE'");
+
+    let err = error("A ADD R0, R0, #1\nA ADD R0, R0, #1");
+    assert_eq!(err.line, 3);
+    assert_eq!(err.message, "duplicate label 'A'");
+
+    assert_eq!(error("1ABC ADD R0, R0, #1").message, "invalid label '1ABC'");
+}
+
+#[test]
+fn rejects_malformed_statements() {
+    assert_eq!(error("FOO BAR R0").message, "unknown mnemonic 'BAR'");
+    assert_eq!(error("FOO BRzn #0").message, "unknown mnemonic 'BRzn'");
+    assert_eq!(
+        error("ADD R0, R0").message,
+        "ADD expects 3 operand(s), found 2"
+    );
+    assert_eq!(
+        error("NOT R0, R8").message,
+        "expected a register, found 'R8'"
+    );
+    assert_eq!(
+        error("ADD R0, R0, LOOP").message,
+        "expected a number, found 'LOOP'"
+    );
+    assert_eq!(error("RET R7").message, "RET expects 0 operand(s), found 1");
+    assert_eq!(error(r#"NOT R0, "R1""#).message, "unexpected string");
+}
+
+#[test]
+fn rejects_bad_directives() {
+    assert_eq!(error(".BLKW 0").message, ".BLKW count out of range");
+    assert_eq!(error(".STRINGZ hello").message, "expected a string");
+    assert_eq!(error(r#".STRINGZ "open"#).message, "unterminated string");
+    assert_eq!(
+        error(r#".STRINGZ "\q""#).message,
+        "unknown escape sequence '\\q'"
+    );
+    assert_eq!(error(".ORIG x4000").message, "duplicate .ORIG");
+}
+
+#[test]
+fn rejects_non_ascii_strings() {
+    let err = error(r#"TEXT .STRINGZ "café""#);
+    assert_eq!(err.line, 2);
+    assert_eq!(err.message, "non-ASCII character 'é' in string");
+}
+
+#[test]
+fn rejects_missing_or_misplaced_origins() {
+    assert_eq!(assemble("").unwrap_err().message, "missing .ORIG");
+    assert_eq!(
+        assemble("HALT\n.ORIG x3000").unwrap_err(),
+        AsmError {
+            line: 1,
+            message: "expected .ORIG before any other statement".to_string()
+        }
+    );
+    assert_eq!(
+        assemble("START .ORIG x3000").unwrap_err().message,
+        ".ORIG cannot be labelled"
+    );
+    assert_eq!(
+        assemble(".ORIG x10000").unwrap_err().message,
+        "origin out of range"
+    );
+    assert_eq!(
+        assemble(".ORIG xFFFF\n.BLKW 2").unwrap_err().message,
+        "program exceeds memory"
+    );
+}
@@ -0,0 +1,176 @@
+use lc3_emu::assembler::assemble;
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+use std::{env, fs, process};
+
+/// Assembles `source` into a temporary object file named after `name`.
+fn object(name: &str, source: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("lc3_emu_cli_{}_{}.obj", process::id(), name));
+    fs::write(&path, assemble(source).unwrap().to_object_bytes()).unwrap();
+    path
+}
+
+/// Runs the emulator binary with `args`, feeding it an empty stdin.
+fn lc3_emu(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_lc3_emu"))
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn headless_run_exits_with_1_when_input_runs_out() {
+    let path = object(
+        "getc",
+        "
+        .ORIG x3000
+        GETC
+        HALT
+        .END
+    ",
+    );
+    let output = lc3_emu(&["--headless", path.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.starts_with("End of input at x3000"), "{}", stderr);
+
+    // the OS routine polls KBSR, which just reports that no key is ready
+    let output = lc3_emu(&[
+        "--headless",
+        "--os",
+        "--max-instructions",
+        "100000",
+        path.to_str().unwrap(),
+    ]);
+    assert_eq!(output.status.code(), Some(3));
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn headless_run_exits_with_0_after_halt() {
+    let path = object(
+        "halt",
+        "
+        .ORIG x3000
+        HALT
+        .END
+    ",
+    );
+    let output = lc3_emu(&["--headless", path.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(output.stdout, b"HALT\n");
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn origin_only_applies_to_a_single_raw_image() {
+    let obj = object(
+        "origin",
+        "
+        .ORIG x3000
+        HALT
+        .END
+    ",
+    );
+    let raw = env::temp_dir().join(format!("lc3_emu_cli_{}_origin.raw", process::id()));
+    fs::write(&raw, [0x25, 0xF0]).unwrap();
+    let (obj_path, raw_path) = (obj.to_str().unwrap(), raw.to_str().unwrap());
+
+    let output = lc3_emu(&["--headless", "--format=raw-le", "--origin=x4000", raw_path]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(output.stdout, b"HALT\n");
+
+    let cases: [(&[&str], &str); 3] = [
+        (
+            &["--format", "raw-le", raw_path],
+            "raw-le and raw-be images require --origin",
+        ),
+        (
+            &["--origin", "x4000", obj_path],
+            "--origin only applies to raw-le and raw-be images",
+        ),
+        (
+            &[
+                "--format", "raw-le", "--origin", "x4000", raw_path, raw_path,
+            ],
+            "--origin applies to a single binary",
+        ),
+    ];
+    for (args, message) in cases {
+        let output = lc3_emu(args);
+        assert_eq!(output.status.code(), Some(2), "{:?}", args);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert_eq!(stderr.trim_end(), message);
+    }
+
+    fs::remove_file(obj).unwrap();
+    fs::remove_file(raw).unwrap();
+}
+
+#[test]
+fn only_options_with_values_take_an_equals_sign() {
+    let path = object(
+        "equals",
+        "
+        .ORIG x3000
+        HALT
+        .END
+    ",
+    );
+    let output = lc3_emu(&["--headless", "--engine=block", path.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(output.stdout, b"HALT\n");
+
+    let output = lc3_emu(&["--headless=1", path.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Invalid argument '--headless=1'"),
+        "{}",
+        stderr
+    );
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn debugged_program_does_not_read_the_debugger_commands() {
+    let path = object(
+        "debug_getc",
+        "
+        .ORIG x3000
+        GETC
+        OUT
+        HALT
+        .END
+    ",
+    );
+    let input = env::temp_dir().join(format!("lc3_emu_cli_{}_debug_getc.txt", process::id()));
+    fs::write(&input, "k").unwrap();
+
+    for (args, expected) in [
+        (vec![], "Program stopped: End of input"),
+        (
+            vec!["--input", input.to_str().unwrap()],
+            "kHALT\nProgram halted",
+        ),
+    ] {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_lc3_emu"))
+            .arg("--debug")
+            .args(&args)
+            .arg(&path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child.stdin.take().unwrap().write_all(b"c\nq\n").unwrap();
+        let output = child.wait_with_output().unwrap();
+        assert_eq!(output.status.code(), Some(0));
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.contains(expected), "{}", stdout);
+    }
+    fs::remove_file(input).unwrap();
+    fs::remove_file(path).unwrap();
+}
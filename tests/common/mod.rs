@@ -0,0 +1,17 @@
+use lc3_emu::assembler::assemble;
+use lc3_emu::{BufferConsole, Emulator, SharedBuffer};
+
+/// Assembles `source` and loads it into a fresh emulator whose console
+/// reads `input`, returning the emulator and a handle to its output.
+pub fn load(source: &str, input: &[u8]) -> (Emulator, SharedBuffer) {
+    let program = assemble(source).unwrap();
+    let console = BufferConsole::new(input);
+    let output = console.output();
+
+    let mut emu = Emulator::new();
+    emu.set_console(console);
+    emu.load_image(&program.to_object_bytes()[..]).unwrap();
+    emu.symbols = program.symbols;
+
+    (emu, output)
+}
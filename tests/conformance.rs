@@ -0,0 +1,488 @@
+use lc3_emu::{BufferConsole, Emulator, Register, TrapMode};
+
+const N: u16 = 0x4;
+const Z: u16 = 0x2;
+const P: u16 = 0x1;
+
+struct Case {
+    name: &'static str,
+    origin: u16,
+    program: &'static [u16],
+    registers: &'static [(Register, u16)],
+    memory: &'static [(u16, u16)],
+    steps: usize,
+    trap_mode: TrapMode,
+    expect_registers: &'static [(Register, u16)],
+    expect_memory: &'static [(u16, u16)],
+}
+
+const BASE: Case = Case {
+    name: "",
+    origin: 0x3000,
+    program: &[],
+    registers: &[],
+    memory: &[],
+    steps: 1,
+    trap_mode: TrapMode::Native,
+    expect_registers: &[],
+    expect_memory: &[],
+};
+
+fn check(cases: &[Case]) {
+    for case in cases {
+        let mut emu = Emulator::new();
+        emu.set_console(BufferConsole::new(b""));
+        emu.trap_mode = case.trap_mode;
+        for &(address, value) in case.memory {
+            emu.write_memory(address, value);
+        }
+        for (i, &word) in case.program.iter().enumerate() {
+            emu.write_memory(case.origin.wrapping_add(i as u16), word);
+        }
+        emu.set_register(Register::Rpc, case.origin);
+        for &(reg, value) in case.registers {
+            emu.set_register(reg, value);
+        }
+
+        for _ in 0..case.steps {
+            if let Err(err) = emu.step() {
+                panic!("{}: {}", case.name, err);
+            }
+        }
+
+        for &(reg, expected) in case.expect_registers {
+            assert_eq!(
+                emu.register(reg),
+                expected,
+                "{}: {:?} is x{:04X}, expected x{:04X}",
+                case.name,
+                reg,
+                emu.register(reg),
+                expected
+            );
+        }
+        for &(address, expected) in case.expect_memory {
+            let actual = emu.memory.peek(address as usize);
+            assert_eq!(
+                actual, expected,
+                "{}: memory x{:04X} is x{:04X}, expected x{:04X}",
+                case.name, address, actual, expected
+            );
+        }
+    }
+}
+
+#[test]
+fn add() {
+    check(&[
+        Case {
+            name: "ADD R0, R1, R2",
+            program: &[0x1042],
+            registers: &[(Register::Rr1, 3), (Register::Rr2, 4)],
+            expect_registers: &[(Register::Rr0, 7), (Register::Rcond, P)],
+            ..BASE
+        },
+        Case {
+            name: "ADD R0, R1, #-5",
+            program: &[0x107B],
+            registers: &[(Register::Rr1, 3)],
+            expect_registers: &[(Register::Rr0, 0xFFFE), (Register::Rcond, N)],
+            ..BASE
+        },
+        Case {
+            name: "ADD wraps to zero",
+            program: &[0x1061],
+            registers: &[(Register::Rr1, 0xFFFF)],
+            expect_registers: &[(Register::Rr0, 0), (Register::Rcond, Z)],
+            ..BASE
+        },
+        Case {
+            name: "ADD overflows into the sign bit",
+            program: &[0x1261],
+            registers: &[(Register::Rr1, 0x7FFF)],
+            expect_registers: &[(Register::Rr1, 0x8000), (Register::Rcond, N)],
+            ..BASE
+        },
+    ]);
+}
+
+#[test]
+fn and() {
+    check(&[
+        Case {
+            name: "AND R3, R1, R2",
+            program: &[0x5642],
+            registers: &[(Register::Rr1, 0x00F0), (Register::Rr2, 0x0FF0)],
+            expect_registers: &[(Register::Rr3, 0x00F0), (Register::Rcond, P)],
+            ..BASE
+        },
+        Case {
+            name: "AND R1, R1, #0",
+            program: &[0x5260],
+            registers: &[(Register::Rr1, 0x1234)],
+            expect_registers: &[(Register::Rr1, 0), (Register::Rcond, Z)],
+            ..BASE
+        },
+        Case {
+            name: "AND R0, R1, #-1",
+            program: &[0x507F],
+            registers: &[(Register::Rr1, 0x8001)],
+            expect_registers: &[(Register::Rr0, 0x8001), (Register::Rcond, N)],
+            ..BASE
+        },
+    ]);
+}
+
+#[test]
+fn not() {
+    check(&[
+        Case {
+            name: "NOT R0, R1",
+            program: &[0x907F],
+            registers: &[(Register::Rr1, 0x00FF)],
+            expect_registers: &[(Register::Rr0, 0xFF00), (Register::Rcond, N)],
+            ..BASE
+        },
+        Case {
+            name: "NOT of all ones",
+            program: &[0x907F],
+            registers: &[(Register::Rr1, 0xFFFF)],
+            expect_registers: &[(Register::Rr0, 0), (Register::Rcond, Z)],
+            ..BASE
+        },
+    ]);
+}
+
+#[test]
+fn br() {
+    check(&[
+        Case {
+            name: "BRz taken",
+            program: &[0x0404],
+            registers: &[(Register::Rcond, Z)],
+            expect_registers: &[(Register::Rpc, 0x3005)],
+            ..BASE
+        },
+        Case {
+            name: "BRn not taken",
+            program: &[0x0804],
+            registers: &[(Register::Rcond, P)],
+            expect_registers: &[(Register::Rpc, 0x3001)],
+            ..BASE
+        },
+        Case {
+            name: "BRnzp backwards",
+            program: &[0x0FFF],
+            registers: &[(Register::Rcond, N)],
+            expect_registers: &[(Register::Rpc, 0x3000)],
+            ..BASE
+        },
+        Case {
+            name: "BR past the top of memory",
+            origin: 0xFFFF,
+            program: &[0x0E02],
+            expect_registers: &[(Register::Rpc, 0x0002)],
+            ..BASE
+        },
+        Case {
+            name: "BR below the bottom of memory",
+            origin: 0x0000,
+            program: &[0x0FFE],
+            expect_registers: &[(Register::Rpc, 0xFFFF)],
+            ..BASE
+        },
+    ]);
+}
+
+#[test]
+fn jmp() {
+    check(&[
+        Case {
+            name: "JMP R2",
+            program: &[0xC080],
+            registers: &[(Register::Rr2, 0x4000)],
+            expect_registers: &[(Register::Rpc, 0x4000)],
+            ..BASE
+        },
+        Case {
+            name: "RET",
+            program: &[0xC1C0],
+            registers: &[(Register::Rr7, 0x5000)],
+            expect_registers: &[(Register::Rpc, 0x5000)],
+            ..BASE
+        },
+    ]);
+}
+
+#[test]
+fn jsr() {
+    check(&[
+        Case {
+            name: "JSR forwards",
+            program: &[0x4810],
+            expect_registers: &[(Register::Rpc, 0x3011), (Register::Rr7, 0x3001)],
+            ..BASE
+        },
+        Case {
+            name: "JSR backwards",
+            program: &[0x4FFF],
+            expect_registers: &[(Register::Rpc, 0x3000), (Register::Rr7, 0x3001)],
+            ..BASE
+        },
+        Case {
+            name: "JSR below the bottom of memory",
+            origin: 0x0000,
+            program: &[0x4FFE],
+            expect_registers: &[(Register::Rpc, 0xFFFF), (Register::Rr7, 0x0001)],
+            ..BASE
+        },
+        Case {
+            name: "JSRR R3",
+            program: &[0x40C0],
+            registers: &[(Register::Rr3, 0x5000)],
+            expect_registers: &[(Register::Rpc, 0x5000), (Register::Rr7, 0x3001)],
+            ..BASE
+        },
+        Case {
+            name: "JSRR R7 jumps to the old R7",
+            program: &[0x41C0],
+            registers: &[(Register::Rr7, 0x6000)],
+            expect_registers: &[(Register::Rpc, 0x6000), (Register::Rr7, 0x3001)],
+            ..BASE
+        },
+    ]);
+}
+
+#[test]
+fn ld() {
+    check(&[
+        Case {
+            name: "LD forwards",
+            program: &[0x2002],
+            memory: &[(0x3003, 0x8000)],
+            expect_registers: &[(Register::Rr0, 0x8000), (Register::Rcond, N)],
+            ..BASE
+        },
+        Case {
+            name: "LD backwards",
+            program: &[0x23FF],
+            expect_registers: &[(Register::Rr1, 0x23FF), (Register::Rcond, P)],
+            ..BASE
+        },
+        Case {
+            name: "LD below the bottom of memory",
+            origin: 0x0000,
+            program: &[0x21FE],
+            memory: &[(0xFFFF, 0x8001)],
+            expect_registers: &[(Register::Rr0, 0x8001), (Register::Rcond, N)],
+            ..BASE
+        },
+    ]);
+}
+
+#[test]
+fn ldi() {
+    check(&[
+        Case {
+            name: "LDI forwards",
+            program: &[0xA401],
+            registers: &[(Register::Rr2, 0x1234)],
+            memory: &[(0x3002, 0x4000), (0x4000, 0x0000)],
+            expect_registers: &[(Register::Rr2, 0), (Register::Rcond, Z)],
+            ..BASE
+        },
+        Case {
+            name: "LDI backwards",
+            program: &[0xA5FF],
+            memory: &[(0xA5FF, 0x0007)],
+            expect_registers: &[(Register::Rr2, 0x0007), (Register::Rcond, P)],
+            ..BASE
+        },
+        Case {
+            name: "LDI below the bottom of memory",
+            origin: 0x0000,
+            program: &[0xA1FE],
+            memory: &[(0xFFFF, 0x4000), (0x4000, 0x8000)],
+            expect_registers: &[(Register::Rr0, 0x8000), (Register::Rcond, N)],
+            ..BASE
+        },
+    ]);
+}
+
+#[test]
+fn ldr() {
+    check(&[
+        Case {
+            name: "LDR with a negative offset",
+            program: &[0x697E],
+            registers: &[(Register::Rr5, 0x4002)],
+            memory: &[(0x4000, 0x1234)],
+            expect_registers: &[(Register::Rr4, 0x1234), (Register::Rcond, P)],
+            ..BASE
+        },
+        Case {
+            name: "LDR past the top of memory",
+            program: &[0x6941],
+            registers: &[(Register::Rr5, 0xFFFF)],
+            memory: &[(0x0000, 0x0042)],
+            expect_registers: &[(Register::Rr4, 0x0042), (Register::Rcond, P)],
+            ..BASE
+        },
+        Case {
+            name: "LDR below the bottom of memory",
+            program: &[0x697F],
+            registers: &[(Register::Rr5, 0x0000)],
+            memory: &[(0xFFFF, 0x8000)],
+            expect_registers: &[(Register::Rr4, 0x8000), (Register::Rcond, N)],
+            ..BASE
+        },
+    ]);
+}
+
+#[test]
+fn lea() {
+    check(&[
+        Case {
+            name: "LEA backwards",
+            program: &[0xE1FD],
+            expect_registers: &[(Register::Rr0, 0x2FFE), (Register::Rcond, P)],
+            ..BASE
+        },
+        Case {
+            name: "LEA below the bottom of memory",
+            origin: 0x0000,
+            program: &[0xE1FE],
+            expect_registers: &[(Register::Rr0, 0xFFFF), (Register::Rcond, N)],
+            ..BASE
+        },
+    ]);
+}
+
+#[test]
+fn st() {
+    check(&[
+        Case {
+            name: "ST forwards",
+            program: &[0x3005],
+            registers: &[(Register::Rr0, 0xBEEF)],
+            expect_memory: &[(0x3006, 0xBEEF)],
+            ..BASE
+        },
+        Case {
+            name: "STI forwards",
+            program: &[0xB201],
+            registers: &[(Register::Rr1, 0x00AA)],
+            memory: &[(0x3002, 0x4000)],
+            expect_memory: &[(0x4000, 0x00AA)],
+            ..BASE
+        },
+        Case {
+            name: "STR with a negative offset",
+            program: &[0x74FF],
+            registers: &[(Register::Rr2, 0x0055), (Register::Rr3, 0x4001)],
+            expect_memory: &[(0x4000, 0x0055)],
+            ..BASE
+        },
+        Case {
+            name: "ST below the bottom of memory",
+            origin: 0x0000,
+            program: &[0x33FE],
+            registers: &[(Register::Rr1, 0x1234)],
+            expect_memory: &[(0xFFFF, 0x1234)],
+            ..BASE
+        },
+        Case {
+            name: "STI backwards",
+            program: &[0xB3FF],
+            registers: &[(Register::Rr1, 0x00AA)],
+            expect_memory: &[(0xB3FF, 0x00AA)],
+            ..BASE
+        },
+        Case {
+            name: "STR past the top of memory",
+            program: &[0x74C2],
+            registers: &[(Register::Rr2, 0x0055), (Register::Rr3, 0xFFFF)],
+            expect_memory: &[(0x0001, 0x0055)],
+            ..BASE
+        },
+    ]);
+}
+
+#[test]
+fn rti() {
+    check(&[
+        Case {
+            name: "RTI back to user mode",
+            program: &[0x8000],
+            registers: &[
+                (Register::Rpsr, 0x0004),
+                (Register::Rr6, 0x2FFE),
+                (Register::RsavedUsp, 0x5000),
+            ],
+            memory: &[(0x2FFE, 0x4000), (0x2FFF, 0x8001)],
+            expect_registers: &[
+                (Register::Rpc, 0x4000),
+                (Register::Rpsr, 0x8001),
+                (Register::Rr6, 0x5000),
+                (Register::RsavedSsp, 0x3000),
+            ],
+            ..BASE
+        },
+        Case {
+            name: "RTI in user mode raises a privilege exception",
+            program: &[0x8000],
+            registers: &[(Register::Rpsr, 0x8002), (Register::Rr6, 0x5000)],
+            memory: &[(0x0100, 0x1000)],
+            expect_registers: &[
+                (Register::Rpc, 0x1000),
+                (Register::Rpsr, 0x0002),
+                (Register::Rr6, 0x2FFE),
+                (Register::RsavedUsp, 0x5000),
+            ],
+            expect_memory: &[(0x2FFF, 0x8002), (0x2FFE, 0x3001)],
+            ..BASE
+        },
+    ]);
+}
+
+#[test]
+fn reserved() {
+    check(&[Case {
+        name: "reserved opcode raises an illegal opcode exception",
+        program: &[0xD000],
+        registers: &[(Register::Rpsr, 0x8001), (Register::Rr6, 0x5000)],
+        memory: &[(0x0101, 0x1100)],
+        expect_registers: &[
+            (Register::Rpc, 0x1100),
+            (Register::Rpsr, 0x0001),
+            (Register::Rr6, 0x2FFE),
+        ],
+        expect_memory: &[(0x2FFF, 0x8001), (0x2FFE, 0x3001)],
+        ..BASE
+    }]);
+}
+
+#[test]
+fn trap() {
+    check(&[Case {
+        name: "TRAP through the vector table",
+        program: &[0xF026],
+        memory: &[(0x0026, 0x0500)],
+        trap_mode: TrapMode::Os,
+        expect_registers: &[(Register::Rpc, 0x0500), (Register::Rr7, 0x3001)],
+        ..BASE
+    }]);
+}
+
+#[test]
+fn self_modifying_code() {
+    check(&[Case {
+        name: "ST over an instruction that already ran",
+        program: &[0x1021, 0x33FE, 0x0FFD],
+        registers: &[(Register::Rr1, 0x1025)],
+        steps: 4,
+        expect_registers: &[(Register::Rr0, 6)],
+        expect_memory: &[(0x3000, 0x1025)],
+        ..BASE
+    }]);
+}
@@ -0,0 +1,180 @@
+mod common;
+
+use lc3_emu::assembler::assemble;
+use lc3_emu::{os, BufferConsole, Emulator, EmulatorError, Register, RunLimits};
+
+const PROGRAM: &str = r#"
+        .ORIG x3000
+        LEA R0, MSG
+        PUTS
+        LEA R0, PACKED
+        PUTSP
+        GETC
+        OUT
+        ADD R1, R0, #0
+        GETC
+        OUT
+        ADD R2, R0, #0
+        HALT
+MSG     .STRINGZ "Hello\n"
+PACKED  .FILL x6261
+        .FILL x0063
+        .FILL x0000
+        .END
+"#;
+
+fn run(with_os: bool, input: &[u8]) -> (Emulator, Result<(), EmulatorError>, String) {
+    let program = assemble(PROGRAM).unwrap();
+    let console = BufferConsole::new(input);
+    let output = console.output();
+
+    let mut emu = Emulator::new();
+    emu.set_console(console);
+    if with_os {
+        os::install(&mut emu);
+    }
+    emu.load_image(&program.to_object_bytes()[..]).unwrap();
+    let result = emu.run();
+
+    (emu, result, output.to_string_lossy())
+}
+
+#[test]
+fn native_traps_use_the_console() {
+    let (emu, result, output) = run(false, b"xy");
+    assert!(result.is_ok());
+    assert_eq!(output, "Hello\nabcxyHALT\n");
+    assert_eq!(emu.register(Register::Rr1), b'x' as u16);
+    assert_eq!(emu.register(Register::Rr2), b'y' as u16);
+}
+
+#[test]
+fn os_traps_use_the_console() {
+    let (emu, result, output) = run(true, b"xy");
+    assert!(result.is_ok());
+    assert_eq!(output, "Hello\nabcxyHALT\n");
+    assert_eq!(emu.register(Register::Rr1), b'x' as u16);
+    assert_eq!(emu.register(Register::Rr2), b'y' as u16);
+}
+
+#[test]
+fn exhausted_input_stops_the_machine() {
+    let (emu, result, output) = run(false, b"x");
+    assert_eq!(output, "Hello\nabcx");
+    assert!(!emu.running);
+    assert!(matches!(
+        result,
+        Err(EmulatorError::EndOfInput { pc: 0x3007, .. })
+    ));
+}
+
+#[test]
+fn os_polling_loop_waits_past_the_end_of_input() {
+    let (mut emu, output) = common::load(PROGRAM, b"x");
+    os::install(&mut emu);
+    let limits = RunLimits {
+        max_instructions: Some(10_000),
+        ..RunLimits::default()
+    };
+    let result = emu.run_limited(limits);
+
+    // polling KBSR only ever reports that no key is ready
+    assert!(matches!(
+        result,
+        Err(EmulatorError::InstructionLimit { .. })
+    ));
+    assert!(emu.running);
+    assert_eq!(output.to_string_lossy(), "Hello\nabcx");
+}
+
+#[test]
+fn puts_wraps_past_the_top_of_memory() {
+    let console = BufferConsole::new(b"");
+    let output = console.output();
+
+    let mut emu = Emulator::new();
+    emu.set_console(console);
+    emu.write_memory(0xFFFF, b'a' as u16);
+    emu.write_memory(0x0000, b'b' as u16);
+    emu.write_memory(0x3000, 0xF022);
+    emu.set_register(Register::Rr0, 0xFFFF);
+    emu.step().unwrap();
+
+    assert_eq!(output.to_string_lossy(), "ab");
+}
+
+#[test]
+fn new_input_resumes_a_machine_that_ran_out() {
+    let (mut emu, result, _) = run(false, b"x");
+    assert!(matches!(result, Err(EmulatorError::EndOfInput { .. })));
+    assert_eq!(emu.register(Register::Rpc), 0x3007);
+
+    let console = BufferConsole::new(b"y");
+    let output = console.output();
+    emu.set_console(console);
+    emu.running = true;
+    emu.run().unwrap();
+    assert_eq!(output.to_string_lossy(), "yHALT\n");
+    assert_eq!(emu.register(Register::Rr2), b'y' as u16);
+}
+
+#[test]
+fn interrupt_driven_programs_need_no_input() {
+    let (mut emu, _) = common::load(
+        "
+        .ORIG x3000
+        LD R0, IE
+        STI R0, KBSR_P
+        ADD R1, R1, #1
+        HALT
+IE      .FILL x4000
+KBSR_P  .FILL xFE00
+        .END
+    ",
+        b"",
+    );
+    emu.run().unwrap();
+    assert_eq!(emu.register(Register::Rr1), 1);
+}
+
+const KEYBOARD: &str = "
+        .ORIG x3000
+        AND R1, R1, #0
+        ADD R1, R1, #10
+POLL    LDI R0, KBSR_P
+        ADD R1, R1, #-1
+        BRp POLL
+        LDI R2, KBDR_P
+        HALT
+KBSR_P  .FILL xFE00
+KBDR_P  .FILL xFE02
+        .END
+";
+
+#[test]
+fn polling_kbsr_after_the_end_of_input_is_not_a_fault() {
+    let (mut emu, _) = common::load(KEYBOARD, b"");
+    let poll = emu.symbols.address_of("POLL").unwrap();
+    for _ in 0..2 {
+        emu.step().unwrap();
+    }
+    while emu.register(Register::Rpc) != poll + 3 {
+        emu.step().unwrap();
+        assert_eq!(emu.register(Register::Rr0), 0);
+    }
+
+    let result = emu.step();
+    assert!(matches!(
+        result,
+        Err(EmulatorError::EndOfInput { pc, .. }) if pc == poll + 3
+    ));
+    assert!(!emu.running);
+}
+
+#[test]
+fn reading_kbdr_waits_for_a_key() {
+    let (mut emu, _) = common::load(KEYBOARD, b"k");
+    emu.write_memory(0x3001, 0x1261); // ADD R1, R1, #1
+    emu.run().unwrap();
+    assert_eq!(emu.register(Register::Rr2), b'k' as u16);
+}
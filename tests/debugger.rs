@@ -0,0 +1,130 @@
+mod common;
+
+use lc3_emu::debugger::Debugger;
+use lc3_emu::{Emulator, Register, SymbolTable};
+
+use std::io::Cursor;
+
+const PROGRAM: &str = "
+        .ORIG x3000
+MAIN    AND R0, R0, #0
+        ADD R0, R0, #3
+LOOP    ADD R0, R0, #-1
+        BRp LOOP
+        JSR DOUBLE
+        HALT
+DOUBLE  ADD R1, R0, R0
+        RET
+        .END
+";
+
+/// Feeds `script` to a debugger attached to [`PROGRAM`] and returns the
+/// machine and everything the debugger printed.
+fn debug(script: &str) -> (Emulator, String) {
+    let (mut emu, _) = common::load(PROGRAM, b"");
+    let mut output = Vec::new();
+    Debugger::new(Cursor::new(script), &mut output)
+        .run(&mut emu)
+        .unwrap();
+    (emu, String::from_utf8(output).unwrap())
+}
+
+#[test]
+fn steps_and_shows_the_next_instruction() {
+    let (emu, output) = debug("step\ns 2\n");
+    assert_eq!(
+        output,
+        "x3000: AND R0, R0, #0\n\
+         (lc3db MAIN) x3001: ADD R0, R0, #3\n\
+         (lc3db MAIN+1) x3003: BRp LOOP\n\
+         (lc3db LOOP+1) \n"
+    );
+    assert_eq!(emu.register(Register::Rr0), 2);
+}
+
+#[test]
+fn continues_to_breakpoints_and_halt() {
+    let (emu, output) = debug("break LOOP+1\nc\nc\nbreakpoints\ndelete x3003\nc\nc\n");
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines[1], "(lc3db MAIN) Breakpoint at x3003");
+    assert_eq!(lines[2], "(lc3db MAIN) Breakpoint hit at LOOP+1");
+    assert_eq!(lines[3], "x3003: BRp LOOP");
+    assert_eq!(lines[4], "(lc3db LOOP+1) Breakpoint hit at LOOP+1");
+    assert_eq!(lines[6], "(lc3db LOOP+1)   x3003");
+    assert_eq!(lines[7], "(lc3db LOOP+1) Deleted breakpoint at x3003");
+    assert_eq!(lines[8], "(lc3db LOOP+1) Program halted");
+    assert_eq!(lines[9], "(lc3db DOUBLE) Program halted");
+    assert!(!emu.running);
+    assert_eq!(emu.register(Register::Rr1), 0);
+}
+
+#[test]
+fn next_and_finish_step_over_subroutines() {
+    let (emu, output) = debug("b x3004\nc\nnext\n");
+    assert!(output.contains("x3005: HALT\n"));
+    assert_eq!(emu.register(Register::Rpc), 0x3005);
+
+    let (emu, output) = debug("b DOUBLE\nc\nfinish\n");
+    assert!(output.contains("Returned from subroutine\nx3005: HALT\n"));
+    assert_eq!(emu.register(Register::Rpc), 0x3005);
+}
+
+#[test]
+fn dumps_registers_memory_and_listings() {
+    let (_, output) = debug("s 2\nregs\nmem x3000 3\nlist LOOP 2\n");
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(
+        lines[2],
+        "(lc3db LOOP) R0 x0003  R1 x0000  R2 x0000  R3 x0000  "
+    );
+    assert_eq!(lines[3], "R4 x0000  R5 x0000  R6 x0000  R7 x0000  ");
+    assert_eq!(lines[4], "PC x3002  PSR x8001 (user, PL0, P)");
+    assert_eq!(lines[5], "(lc3db LOOP) x3000: x5020 x1023 x103F");
+    assert_eq!(
+        lines[6],
+        "(lc3db LOOP) x3002  x103F  LOOP         ADD R0, R0, #-1"
+    );
+    assert_eq!(lines[7], "x3003  x03FE               BRp LOOP");
+}
+
+#[test]
+fn edits_registers_and_memory() {
+    let (emu, output) = debug("set R2 x1234\nset LOOP #-1\nset R9 1\nset PC bad\n");
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines[1], "(lc3db MAIN) R2 = x1234");
+    assert_eq!(lines[2], "(lc3db MAIN) [x3002] = xFFFF");
+    assert_eq!(lines[3], "(lc3db MAIN) Unknown register or address 'R9'");
+    assert_eq!(lines[4], "(lc3db MAIN) Invalid value 'bad'");
+    assert_eq!(emu.register(Register::Rr2), 0x1234);
+    assert_eq!(emu.memory.peek(0x3002), 0xFFFF);
+}
+
+#[test]
+fn reports_bad_commands_and_quits() {
+    let (emu, output) = debug("frobnicate\nstep 0\nbreak NOWHERE\nquit\nstep\n");
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(
+        lines[1],
+        "(lc3db MAIN) Unknown command 'frobnicate'. Try 'help'."
+    );
+    assert_eq!(lines[2], "(lc3db MAIN) Invalid step count '0'");
+    assert_eq!(lines[3], "(lc3db MAIN) Usage: break <addr|label>");
+    assert_eq!(lines.len(), 5);
+    assert_eq!(emu.register(Register::Rpc), 0x3000);
+}
+
+#[test]
+fn with_symbols_adds_labels_to_the_session() {
+    let mut emu = Emulator::new();
+    let mut symbols = SymbolTable::new();
+    symbols.insert("START", 0x3000);
+    let mut output = Vec::new();
+    Debugger::new(Cursor::new("break START+1\n"), &mut output)
+        .with_symbols(symbols)
+        .run(&mut emu)
+        .unwrap();
+
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("(lc3db START) Breakpoint at x3001"));
+    assert_eq!(emu.symbols.address_of("START"), Some(0x3000));
+}
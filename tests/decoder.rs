@@ -0,0 +1,96 @@
+use lc3_emu::assembler::assemble;
+use lc3_emu::{decode, encode, Instruction, Operand, Register};
+
+#[test]
+fn decodes_typed_operands() {
+    assert_eq!(
+        decode(0x1261),
+        Instruction::Add {
+            dr: Register::Rr1,
+            sr1: Register::Rr1,
+            src2: Operand::Immediate(1),
+        }
+    );
+    assert_eq!(
+        decode(0x5642),
+        Instruction::And {
+            dr: Register::Rr3,
+            sr1: Register::Rr1,
+            src2: Operand::Register(Register::Rr2),
+        }
+    );
+    assert_eq!(
+        decode(0x0BFE),
+        Instruction::Br {
+            n: true,
+            z: false,
+            p: true,
+            offset: -2,
+        }
+    );
+    assert_eq!(
+        decode(0x697E),
+        Instruction::Ldr {
+            dr: Register::Rr4,
+            base: Register::Rr5,
+            offset: -2,
+        }
+    );
+    assert_eq!(decode(0x4FFF), Instruction::Jsr { offset: -1 });
+    assert_eq!(
+        decode(0x41C0),
+        Instruction::Jsrr {
+            base: Register::Rr7
+        }
+    );
+    assert_eq!(decode(0xF025), Instruction::Trap { vector: 0x25 });
+    assert_eq!(decode(0xD123), Instruction::Reserved);
+}
+
+#[test]
+fn assembled_instructions_round_trip() {
+    let program = assemble(
+        "
+        .ORIG x3000
+START   ADD R0, R1, R2
+        ADD R3, R4, #-16
+        AND R5, R6, R7
+        AND R0, R0, #15
+        NOT R1, R2
+        BR START
+        BRn START
+        BRzp START
+        NOP
+        JMP R3
+        RET
+        JSR START
+        JSRR R4
+        LD R0, START
+        LDI R1, START
+        LEA R2, START
+        ST R3, START
+        STI R4, START
+        LDR R5, R6, #-32
+        STR R7, R0, #31
+        RTI
+        TRAP x30
+        HALT
+        .END
+    ",
+    )
+    .unwrap();
+
+    assert_eq!(program.words.len(), 23);
+    assert_eq!(program.words[8], 0x0000);
+    for &word in &program.words {
+        assert_eq!(encode(decode(word)), word, "x{:04X}", word);
+    }
+}
+
+#[test]
+fn every_word_decodes_to_a_stable_instruction() {
+    for word in 0..=u16::MAX {
+        let instruction = decode(word);
+        assert_eq!(decode(encode(instruction)), instruction, "x{:04X}", word);
+    }
+}
@@ -0,0 +1,98 @@
+mod common;
+
+use lc3_emu::{MemoryMappedRegister, Register};
+
+#[test]
+fn only_the_interrupt_enable_bit_of_kbsr_is_writable() {
+    let (mut emu, _) = common::load(
+        "
+        .ORIG x3000
+        LD R0, VALUE
+        STI R0, KBSR_P
+        LDI R1, KBSR_P
+        HALT
+VALUE   .FILL xFFFF
+KBSR_P  .FILL xFE00
+        .END
+    ",
+        b"",
+    );
+    emu.run().unwrap();
+    assert_eq!(emu.register(Register::Rr1), 0x4000);
+}
+
+#[test]
+fn writes_to_kbdr_are_ignored() {
+    let (mut emu, _) = common::load(
+        "
+        .ORIG x3000
+        LD R0, VALUE
+        STI R0, KBDR_P
+        HALT
+VALUE   .FILL x1234
+KBDR_P  .FILL xFE02
+        .END
+    ",
+        b"",
+    );
+    emu.run().unwrap();
+    assert_eq!(emu.memory.peek(MemoryMappedRegister::Kbdr as usize), 0);
+}
+
+#[test]
+fn writes_to_dsr_are_ignored() {
+    let (mut emu, _) = common::load(
+        "
+        .ORIG x3000
+        AND R0, R0, #0
+        STI R0, DSR_P
+        LDI R1, DSR_P
+        HALT
+DSR_P   .FILL xFE04
+        .END
+    ",
+        b"",
+    );
+    emu.run().unwrap();
+    assert_eq!(emu.register(Register::Rr1), 0x8000);
+}
+
+#[test]
+fn writing_ddr_prints_the_character() {
+    let (mut emu, output) = common::load(
+        "
+        .ORIG x3000
+        LD R0, CHAR
+        STI R0, DDR_P
+        HALT
+CHAR    .FILL x0041
+DDR_P   .FILL xFE06
+        .END
+    ",
+        b"",
+    );
+    emu.run().unwrap();
+    assert_eq!(output.to_string_lossy(), "AHALT\n");
+}
+
+#[test]
+fn clearing_the_clock_enable_bit_of_mcr_halts() {
+    let (mut emu, output) = common::load(
+        "
+        .ORIG x3000
+        LD R0, CLOCK
+        STI R0, MCR_P
+        ADD R1, R1, #1
+        HALT
+CLOCK   .FILL x7FFF
+MCR_P   .FILL xFFFE
+        .END
+    ",
+        b"",
+    );
+    emu.run().unwrap();
+    assert!(!emu.running);
+    assert_eq!(emu.register(Register::Rpc), 0x3002);
+    assert_eq!(emu.register(Register::Rr1), 0);
+    assert_eq!(output.to_string_lossy(), "");
+}
@@ -0,0 +1,100 @@
+use lc3_emu::disassembler::{disassemble, disassemble_memory, disassemble_words};
+use lc3_emu::Emulator;
+
+fn at_x3000(word: u16) -> String {
+    disassemble(0x3000, word, None)
+}
+
+#[test]
+fn disassembles_operate_instructions() {
+    assert_eq!(at_x3000(0x1283), "ADD R1, R2, R3");
+    assert_eq!(at_x3000(0x12B0), "ADD R1, R2, #-16");
+    assert_eq!(at_x3000(0x1E2F), "ADD R7, R0, #15");
+    assert_eq!(at_x3000(0x5946), "AND R4, R5, R6");
+    assert_eq!(at_x3000(0x5960), "AND R4, R5, #0");
+    assert_eq!(at_x3000(0x91FF), "NOT R0, R7");
+}
+
+#[test]
+fn disassembles_every_branch_condition() {
+    let cases = [
+        (0x0000, "NOP"),
+        (0x0205, "BRp x3006"),
+        (0x0405, "BRz x3006"),
+        (0x0605, "BRzp x3006"),
+        (0x0805, "BRn x3006"),
+        (0x0A05, "BRnp x3006"),
+        (0x0C05, "BRnz x3006"),
+        (0x0E05, "BRnzp x3006"),
+    ];
+    for (word, text) in cases {
+        assert_eq!(at_x3000(word), text, "x{:04X}", word);
+    }
+}
+
+#[test]
+fn resolves_negative_offsets() {
+    assert_eq!(at_x3000(0x0FFF), "BRnzp x3000");
+    assert_eq!(at_x3000(0x0F00), "BRnzp x2F01");
+    assert_eq!(at_x3000(0x21FE), "LD R0, x2FFF");
+    assert_eq!(at_x3000(0x4C00), "JSR x2C01");
+    assert_eq!(at_x3000(0x6DE0), "LDR R6, R7, #-32");
+    assert_eq!(at_x3000(0x7E7F), "STR R7, R1, #-1");
+    // PC-relative targets wrap around the address space
+    assert_eq!(disassemble(0x0000, 0xE1FE, None), "LEA R0, xFFFF");
+}
+
+#[test]
+fn tells_ret_from_jmp_and_jsr_from_jsrr() {
+    assert_eq!(at_x3000(0xC1C0), "RET");
+    assert_eq!(at_x3000(0xC080), "JMP R2");
+    assert_eq!(at_x3000(0x4802), "JSR x3003");
+    assert_eq!(at_x3000(0x40C0), "JSRR R3");
+    assert_eq!(at_x3000(0x41C0), "JSRR R7");
+}
+
+#[test]
+fn disassembles_memory_instructions() {
+    assert_eq!(at_x3000(0x2007), "LD R0, x3008");
+    assert_eq!(at_x3000(0xA206), "LDI R1, x3007");
+    assert_eq!(at_x3000(0xE405), "LEA R2, x3006");
+    assert_eq!(at_x3000(0x3604), "ST R3, x3005");
+    assert_eq!(at_x3000(0xB803), "STI R4, x3004");
+    assert_eq!(at_x3000(0x705F), "STR R0, R1, #31");
+}
+
+#[test]
+fn disassembles_system_instructions() {
+    assert_eq!(at_x3000(0x8000), "RTI");
+    assert_eq!(at_x3000(0xF020), "GETC");
+    assert_eq!(at_x3000(0xF021), "OUT");
+    assert_eq!(at_x3000(0xF022), "PUTS");
+    assert_eq!(at_x3000(0xF023), "IN");
+    assert_eq!(at_x3000(0xF024), "PUTSP");
+    assert_eq!(at_x3000(0xF025), "HALT");
+    assert_eq!(at_x3000(0xF0FF), "TRAP xFF");
+}
+
+#[test]
+fn reserved_opcode_is_a_fill() {
+    assert_eq!(at_x3000(0xD000), ".FILL xD000");
+    assert_eq!(at_x3000(0xDABC), ".FILL xDABC");
+}
+
+#[test]
+fn lists_words_with_addresses() {
+    let lines = disassemble_words(0x3000, &[0x1021, 0xF025], None);
+    assert_eq!(
+        lines,
+        [
+            "x3000  x1021               ADD R0, R0, #1",
+            "x3001  xF025               HALT",
+        ]
+    );
+
+    let mut emu = Emulator::new();
+    emu.write_memory(0xFFFF, 0xC1C0);
+    let lines = disassemble_memory(&emu.memory, 0xFFFF, 2, None);
+    assert_eq!(lines[0], "xFFFF  xC1C0               RET");
+    assert_eq!(lines[1], "x0000  x0000               NOP");
+}
@@ -0,0 +1,173 @@
+mod common;
+
+use lc3_emu::{os, Emulator, EmulatorError, Engine, Register, RunLimits};
+
+fn run(
+    source: &str,
+    engine: Engine,
+    with_os: bool,
+    limits: RunLimits,
+) -> (Emulator, Result<(), EmulatorError>, String) {
+    let (mut emu, output) = common::load(source, b"xy");
+    emu.engine = engine;
+    if with_os {
+        os::install(&mut emu);
+    }
+    let result = emu.run_limited(limits);
+
+    (emu, result, output.to_string_lossy())
+}
+
+/// Runs `source` with both engines and checks they end in the same state.
+fn same_as_interpreter(source: &str, with_os: bool, limits: RunLimits) -> (Emulator, String) {
+    let (expected, expected_result, expected_output) =
+        run(source, Engine::Interpreter, with_os, limits);
+    let (emu, result, output) = run(source, Engine::Block, with_os, limits);
+
+    assert_eq!(format!("{:?}", result), format!("{:?}", expected_result));
+    assert_eq!(output, expected_output);
+    for reg in (0..8)
+        .map(Register::general)
+        .chain([Register::Rpc, Register::Rpsr])
+    {
+        assert_eq!(emu.register(reg), expected.register(reg), "{:?}", reg);
+    }
+    for address in 0x3000..0x3100 {
+        assert_eq!(
+            emu.memory.peek(address),
+            expected.memory.peek(address),
+            "x{:04X}",
+            address
+        );
+    }
+    (emu, output)
+}
+
+const PROGRAM: &str = r#"
+        .ORIG x3000
+        LEA R0, MSG
+        PUTS
+        LEA R1, DATA
+        AND R2, R2, #0
+        ADD R3, R2, #5
+SUM     LDR R4, R1, #0
+        ADD R2, R2, R4
+        NOT R4, R4
+        STR R4, R1, #0
+        ADD R1, R1, #1
+        ADD R3, R3, #-1
+        BRp SUM
+        ST R2, TOTAL
+        LDI R5, TOTAL_P
+        STI R5, COPY_P
+        JSR DOUBLE
+        LEA R6, DOUBLE
+        JSRR R6
+        GETC
+        OUT
+        HALT
+DOUBLE  ADD R5, R5, R5
+        RET
+MSG     .STRINGZ "Sum\n"
+TOTAL   .FILL 0
+TOTAL_P .FILL TOTAL
+COPY_P  .FILL COPY
+COPY    .FILL 0
+DATA    .FILL 1
+        .FILL 2
+        .FILL 3
+        .FILL 4
+        .FILL 5
+        .END
+"#;
+
+#[test]
+fn block_engine_matches_the_interpreter() {
+    let (emu, output) = same_as_interpreter(PROGRAM, false, RunLimits::default());
+    assert_eq!(output, "Sum\nxHALT\n");
+    assert_eq!(emu.register(Register::Rr2), 15);
+    assert_eq!(emu.register(Register::Rr5), 60);
+}
+
+#[test]
+fn block_engine_runs_the_os_traps() {
+    let (_, output) = same_as_interpreter(PROGRAM, true, RunLimits::default());
+    assert_eq!(output, "Sum\nxHALT\n");
+}
+
+#[test]
+fn block_engine_hands_device_registers_to_the_interpreter() {
+    let (emu, output) = same_as_interpreter(
+        "
+        .ORIG x3000
+        LD R0, CHAR_A
+WAIT    LDI R1, DSR_P
+        BRzp WAIT
+        STI R0, DDR_P
+        ADD R0, R0, #1
+        LD R2, DDR
+        STR R0, R2, #0
+        AND R0, R0, #0
+        STI R0, MCR_P
+        ADD R3, R3, #1
+CHAR_A  .FILL x61
+DSR_P   .FILL xFE04
+DDR_P   .FILL xFE06
+DDR     .FILL xFE06
+MCR_P   .FILL xFFFE
+        .END
+    ",
+        false,
+        RunLimits::default(),
+    );
+    assert_eq!(output, "ab");
+    assert!(!emu.running);
+    assert_eq!(emu.register(Register::Rr3), 0);
+}
+
+#[test]
+fn block_engine_sees_self_modifying_code() {
+    let (emu, _) = same_as_interpreter(
+        "
+        .ORIG x3000
+        AND R0, R0, #0
+        AND R2, R2, #0
+        ADD R2, R2, #2
+LOOP    LD R1, PATCH
+        ST R1, TARGET
+TARGET  ADD R0, R0, #1
+        ADD R2, R2, #-1
+        BRp LOOP
+        HALT
+PATCH   ADD R0, R0, #5
+        .END
+    ",
+        false,
+        RunLimits::default(),
+    );
+    assert_eq!(emu.register(Register::Rr0), 10);
+}
+
+#[test]
+fn block_engine_stops_at_the_instruction_limit() {
+    let limits = RunLimits {
+        max_instructions: Some(7),
+        ..RunLimits::default()
+    };
+    let (emu, _) = same_as_interpreter(
+        "
+        .ORIG x3000
+LOOP    ADD R0, R0, #1
+        ADD R0, R0, #1
+        ADD R0, R0, #1
+        ADD R0, R0, #1
+        ADD R0, R0, #1
+        BRnzp LOOP
+        .END
+    ",
+        false,
+        limits,
+    );
+    assert_eq!(emu.register(Register::Rr0), 6);
+    assert_eq!(emu.register(Register::Rpc), 0x3001);
+}
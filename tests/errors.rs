@@ -0,0 +1,165 @@
+use lc3_emu::assembler::assemble;
+use lc3_emu::{
+    BufferConsole, Emulator, EmulatorError, LoadError, Register, RunLimits, SharedBuffer,
+    StreamConsole,
+};
+
+use std::io::{self, Read};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Input that never produces a key and never ends.
+struct Silent;
+
+impl Read for Silent {
+    fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+        loop {
+            thread::park();
+        }
+    }
+}
+
+fn run(source: &str) -> Result<(), EmulatorError> {
+    let program = assemble(source).unwrap();
+    let mut emu = Emulator::new();
+    emu.set_console(BufferConsole::new(b""));
+    emu.load_image(&program.to_object_bytes()[..]).unwrap();
+    emu.run()
+}
+
+#[test]
+fn empty_image_is_a_load_error() {
+    let mut emu = Emulator::new();
+    assert!(matches!(
+        emu.load_image(&[][..]),
+        Err(EmulatorError::Load(LoadError::Truncated))
+    ));
+}
+
+#[test]
+fn invalid_trap_vector_reports_the_instruction() {
+    let err = run("
+        .ORIG x3000
+        ADD R0, R0, #1
+        TRAP x30
+        .END
+    ")
+    .unwrap_err();
+    assert!(matches!(
+        err,
+        EmulatorError::InvalidTrapVector {
+            pc: 0x3001,
+            instruction: 0xF030
+        }
+    ));
+    assert_eq!(err.to_string(), "Invalid trap vector x30");
+}
+
+#[test]
+fn rti_in_user_mode_is_a_privilege_violation() {
+    let err = run("
+        .ORIG x3000
+        RTI
+        .END
+    ")
+    .unwrap_err();
+    assert_eq!(err.pc(), Some(0x3000));
+    assert_eq!(err.instruction(), Some(0x8000));
+    assert!(matches!(err, EmulatorError::PrivilegeViolation { .. }));
+}
+
+#[test]
+fn reserved_opcode_is_an_invalid_opcode() {
+    let err = run("
+        .ORIG x3000
+        .FILL xD123
+        .END
+    ")
+    .unwrap_err();
+    assert!(matches!(
+        err,
+        EmulatorError::InvalidOpcode {
+            pc: 0x3000,
+            instruction: 0xD123
+        }
+    ));
+}
+
+#[test]
+fn instruction_limit_stops_a_runaway_loop() {
+    let program = assemble(
+        "
+        .ORIG x3000
+        ADD R0, R0, #1
+LOOP    BRnzp LOOP
+        .END
+    ",
+    )
+    .unwrap();
+    let mut emu = Emulator::new();
+    emu.load_image(&program.to_object_bytes()[..]).unwrap();
+    let limits = RunLimits {
+        max_instructions: Some(100),
+        ..RunLimits::default()
+    };
+    assert!(matches!(
+        emu.run_limited(limits),
+        Err(EmulatorError::InstructionLimit {
+            pc: 0x3001,
+            limit: 100
+        })
+    ));
+    assert_eq!(emu.register(Register::Rr0), 1);
+}
+
+#[test]
+fn timeout_stops_a_runaway_loop() {
+    let program = assemble(
+        "
+        .ORIG x3000
+LOOP    BRnzp LOOP
+        .END
+    ",
+    )
+    .unwrap();
+    let mut emu = Emulator::new();
+    emu.load_image(&program.to_object_bytes()[..]).unwrap();
+    let limits = RunLimits {
+        timeout: Some(Duration::from_millis(20)),
+        ..RunLimits::default()
+    };
+    assert!(matches!(
+        emu.run_limited(limits),
+        Err(EmulatorError::Timeout { pc: 0x3000, .. })
+    ));
+}
+
+#[test]
+fn timeout_interrupts_getc_waiting_for_input() {
+    let program = assemble(
+        "
+        .ORIG x3000
+        GETC
+        HALT
+        .END
+    ",
+    )
+    .unwrap();
+    let mut emu = Emulator::new();
+    emu.load_image(&program.to_object_bytes()[..]).unwrap();
+    emu.set_console(StreamConsole::new(Silent, SharedBuffer::new()));
+    let limits = RunLimits {
+        timeout: Some(Duration::from_millis(50)),
+        ..RunLimits::default()
+    };
+
+    let start = Instant::now();
+    assert!(matches!(
+        emu.run_limited(limits),
+        Err(EmulatorError::Timeout { pc: 0x3000, .. })
+    ));
+    assert!(start.elapsed() < Duration::from_secs(5));
+    // the GETC runs again on resuming
+    assert!(emu.running);
+    assert_eq!(emu.register(Register::Rpc), 0x3000);
+}
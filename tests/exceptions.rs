@@ -0,0 +1,172 @@
+mod common;
+
+use lc3_emu::{Emulator, Register};
+
+const PRIVILEGED: &str = "
+        .ORIG x3000
+        LD R6, USER_SP
+        RTI
+        ADD R1, R1, #1
+        HALT
+HANDLER ADD R2, R2, #1
+        RTI
+USER_SP .FILL x4000
+        .END
+";
+
+/// Loads `source` and points the vector table entry `vector` at its
+/// `HANDLER` label.
+fn with_handler(source: &str, vector: u16) -> Emulator {
+    let (mut emu, _) = common::load(source, b"");
+    let handler = emu.symbols.address_of("HANDLER").unwrap();
+    emu.write_memory(0x0100 + vector, handler);
+    emu
+}
+
+fn step(emu: &mut Emulator, count: usize) {
+    for _ in 0..count {
+        emu.step().unwrap();
+    }
+}
+
+#[test]
+fn privilege_violation_enters_the_handler_on_the_supervisor_stack() {
+    let mut emu = with_handler(PRIVILEGED, 0x00);
+    step(&mut emu, 2);
+
+    assert_eq!(emu.register(Register::Rpc), 0x3004);
+    assert!(!emu.registers.user_mode());
+    assert_eq!(emu.registers.priority(), 0);
+    assert_eq!(emu.register(Register::Rr6), 0x2FFE);
+    assert_eq!(emu.register(Register::RsavedUsp), 0x4000);
+    assert_eq!(emu.memory.peek(0x2FFF), 0x8001);
+    assert_eq!(emu.memory.peek(0x2FFE), 0x3002);
+}
+
+#[test]
+fn rti_returns_to_user_mode_and_the_user_stack() {
+    let mut emu = with_handler(PRIVILEGED, 0x00);
+    step(&mut emu, 4);
+
+    assert_eq!(emu.register(Register::Rpc), 0x3002);
+    assert_eq!(emu.register(Register::Rpsr), 0x8001);
+    assert_eq!(emu.register(Register::Rr6), 0x4000);
+    assert_eq!(emu.register(Register::RsavedSsp), 0x3000);
+
+    emu.run().unwrap();
+    assert_eq!(emu.register(Register::Rr1), 1);
+    assert_eq!(emu.register(Register::Rr2), 1);
+}
+
+#[test]
+fn rti_within_supervisor_mode_keeps_the_stack() {
+    let (mut emu, _) = common::load(
+        "
+        .ORIG x3000
+        RTI
+        .END
+    ",
+        b"",
+    );
+    // supervisor mode at PL3, returning to supervisor mode at PL1
+    emu.set_register(Register::Rpsr, 0x0302);
+    emu.set_register(Register::Rr6, 0x2FF0);
+    emu.push(0x0104);
+    emu.push(0x4000);
+    step(&mut emu, 1);
+
+    assert_eq!(emu.register(Register::Rpc), 0x4000);
+    assert_eq!(emu.register(Register::Rpsr), 0x0104);
+    assert!(!emu.registers.user_mode());
+    assert_eq!(emu.registers.priority(), 1);
+    assert_eq!(emu.register(Register::Rr6), 0x2FF0);
+    assert_eq!(emu.register(Register::RsavedSsp), 0x3000);
+}
+
+const KEYBOARD: &str = "
+        .ORIG x3000
+        LD R0, IE
+        STI R0, KBSR_P
+        ADD R1, R1, #1
+        ADD R1, R1, #1
+        HALT
+HANDLER LDI R2, KBDR_P
+        RTI
+IE      .FILL x4000
+KBSR_P  .FILL xFE00
+KBDR_P  .FILL xFE02
+        .END
+";
+
+#[test]
+fn keyboard_interrupt_enters_the_handler_at_pl4() {
+    let (mut emu, _) = common::load(KEYBOARD, b"k");
+    let handler = emu.symbols.address_of("HANDLER").unwrap();
+    emu.write_memory(0x0180, handler);
+    step(&mut emu, 2);
+    assert_eq!(emu.pending_interrupt(), None);
+
+    // the key is latched before the next instruction, which is the
+    // handler's first
+    step(&mut emu, 1);
+    assert_eq!(emu.register(Register::Rpc), handler + 1);
+    assert_eq!(emu.register(Register::Rr2), b'k' as u16);
+    assert!(!emu.registers.user_mode());
+    assert_eq!(emu.registers.priority(), 4);
+    assert_eq!(emu.register(Register::Rr6), 0x2FFE);
+    assert_eq!(emu.memory.peek(0x2FFF), 0x8001);
+    assert_eq!(emu.memory.peek(0x2FFE), 0x3002);
+
+    step(&mut emu, 1);
+    assert_eq!(emu.register(Register::Rpc), 0x3002);
+    assert_eq!(emu.register(Register::Rpsr), 0x8001);
+
+    emu.run().unwrap();
+    assert_eq!(emu.register(Register::Rr1), 2);
+}
+
+#[test]
+fn keyboard_interrupt_waits_for_a_lower_priority_level() {
+    let (mut emu, _) = common::load(KEYBOARD, b"k");
+    let handler = emu.symbols.address_of("HANDLER").unwrap();
+    emu.write_memory(0x0180, handler);
+    step(&mut emu, 2);
+
+    for priority in [4, 7] {
+        emu.set_register(Register::Rpsr, 0x8001 | priority << 8);
+        emu.memory.poll_keyboard();
+        assert_eq!(emu.pending_interrupt(), None, "PL{}", priority);
+    }
+
+    emu.set_register(Register::Rpsr, 0x8301);
+    step(&mut emu, 1);
+    assert_eq!(emu.register(Register::Rpc), handler + 1);
+    assert_eq!(emu.registers.priority(), 4);
+    assert_eq!(emu.memory.peek(0x2FFF), 0x8301);
+}
+
+#[test]
+fn illegal_opcode_enters_its_handler() {
+    let mut emu = with_handler(
+        "
+        .ORIG x3000
+        .FILL xD000
+        HALT
+HANDLER ADD R2, R2, #1
+        RTI
+        .END
+    ",
+        0x01,
+    );
+    emu.set_register(Register::Rpsr, 0x8202);
+    step(&mut emu, 1);
+
+    assert_eq!(emu.register(Register::Rpc), 0x3002);
+    assert!(!emu.registers.user_mode());
+    assert_eq!(emu.registers.priority(), 2);
+    assert_eq!(emu.memory.peek(0x2FFF), 0x8202);
+    assert_eq!(emu.memory.peek(0x2FFE), 0x3001);
+
+    emu.run().unwrap();
+    assert_eq!(emu.register(Register::Rr2), 1);
+}
@@ -0,0 +1,176 @@
+use lc3_emu::assembler::assemble;
+use lc3_emu::{gdb, Emulator};
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+
+const PROGRAM: &str = "
+        .ORIG x3000
+        AND R0, R0, #0
+        ADD R0, R0, #5
+        ADD R1, R0, #2
+LOOP    ADD R0, R0, #-1
+        BRp LOOP
+        HALT
+        .END
+";
+
+struct Client {
+    stream: TcpStream,
+}
+
+impl Client {
+    fn request(&mut self, data: &str) -> String {
+        let checksum = data.bytes().fold(0u8, |sum, b| sum.wrapping_add(b));
+        let packet = format!("${}#{:02x}", data, checksum);
+        self.stream.write_all(packet.as_bytes()).unwrap();
+
+        let mut byte = [0; 1];
+        self.stream.read_exact(&mut byte).unwrap();
+        assert_eq!(byte[0], b'+', "packet '{}' was not acknowledged", data);
+
+        self.stream.read_exact(&mut byte).unwrap();
+        assert_eq!(byte[0], b'$');
+        let mut reply = Vec::new();
+        loop {
+            self.stream.read_exact(&mut byte).unwrap();
+            if byte[0] == b'#' {
+                break;
+            }
+            reply.push(byte[0]);
+        }
+        let mut checksum = [0; 2];
+        self.stream.read_exact(&mut checksum).unwrap();
+        let expected = reply.iter().fold(0u8, |sum, &b| sum.wrapping_add(b));
+        assert_eq!(
+            u8::from_str_radix(std::str::from_utf8(&checksum).unwrap(), 16).unwrap(),
+            expected
+        );
+        self.stream.write_all(b"+").unwrap();
+
+        String::from_utf8(reply).unwrap()
+    }
+}
+
+fn start() -> (Client, thread::JoinHandle<Emulator>) {
+    let program = assemble(PROGRAM).unwrap();
+    let mut emu = Emulator::new();
+    emu.load_image(&program.to_object_bytes()[..]).unwrap();
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let server = thread::spawn(move || {
+        gdb::serve(&listener, &mut emu).unwrap();
+        emu
+    });
+
+    let stream = TcpStream::connect(address).unwrap();
+    stream.set_nodelay(true).unwrap();
+    (Client { stream }, server)
+}
+
+#[test]
+fn reads_registers_and_single_steps() {
+    let (mut client, server) = start();
+
+    assert_eq!(client.request("?"), "S05");
+    let registers = client.request("g");
+    assert_eq!(registers.len(), 40);
+    assert_eq!(&registers[32..36], "0030");
+
+    assert_eq!(client.request("s"), "S05");
+    assert_eq!(client.request("s"), "S05");
+    assert_eq!(client.request("p0"), "0500");
+    assert_eq!(client.request("p8"), "0230");
+
+    assert_eq!(client.request("D"), "OK");
+    server.join().unwrap();
+}
+
+#[test]
+fn reads_and_writes_memory_and_registers() {
+    let (mut client, server) = start();
+
+    assert_eq!(client.request("m3000,4"), "20502510");
+    assert_eq!(client.request("M4000,4:3412cdab"), "OK");
+    assert_eq!(client.request("m4000,4"), "3412cdab");
+    assert_eq!(client.request("P3=efbe"), "OK");
+    assert_eq!(client.request("p3"), "efbe");
+    assert_eq!(client.request("m3000,3"), "E01");
+    assert_eq!(client.request("m10000,2"), "E01");
+    assert_eq!(client.request("m0,FFFFFFFF"), "E01");
+    // reads are cut short to fit the advertised packet size
+    assert!(client.request("qSupported").starts_with("PacketSize=1000;"));
+    assert_eq!(client.request("m0,20000").len(), 0x1000);
+
+    assert_eq!(client.request("D"), "OK");
+    let emu = server.join().unwrap();
+    assert_eq!(emu.memory.peek(0x4000), 0x1234);
+    assert_eq!(emu.memory.peek(0x4001), 0xABCD);
+    assert_eq!(emu.register(lc3_emu::Register::Rr3), 0xBEEF);
+}
+
+#[test]
+fn stops_at_breakpoints_and_reports_halt() {
+    let (mut client, server) = start();
+
+    assert_eq!(client.request("Z0,3005,2"), "OK");
+    assert_eq!(client.request("c"), "S05");
+    assert_eq!(client.request("p8"), "0530");
+    assert_eq!(client.request("p0"), "0000");
+    assert_eq!(client.request("p1"), "0700");
+
+    assert_eq!(client.request("z0,3005,2"), "OK");
+    assert_eq!(client.request("c"), "W00");
+    assert_eq!(client.request("?"), "W00");
+
+    client.stream.write_all(b"$k#6b").unwrap();
+    let emu = server.join().unwrap();
+    assert!(!emu.running);
+}
+
+#[test]
+fn rejects_addresses_past_the_address_space() {
+    let (mut client, server) = start();
+
+    assert_eq!(client.request("s10000"), "E01");
+    assert_eq!(client.request("c13000"), "E01");
+    assert_eq!(client.request("Z0,13005,2"), "E01");
+    assert_eq!(client.request("p8"), "0030");
+    assert_eq!(client.request("s3001"), "S05");
+    assert_eq!(client.request("p8"), "0230");
+
+    assert_eq!(client.request("D"), "OK");
+    server.join().unwrap();
+}
+
+#[test]
+fn asks_for_corrupted_packets_again() {
+    let (mut client, server) = start();
+
+    let mut byte = [0; 1];
+    for _ in 0..10_000 {
+        client.stream.write_all(b"$?#00").unwrap();
+        client.stream.read_exact(&mut byte).unwrap();
+        assert_eq!(byte[0], b'-');
+    }
+    assert_eq!(client.request("?"), "S05");
+
+    assert_eq!(client.request("D"), "OK");
+    server.join().unwrap();
+}
+
+#[test]
+fn memory_transfers_stop_at_the_end_of_memory() {
+    let (mut client, server) = start();
+
+    assert_eq!(client.request("MFFFF,2:3412"), "OK");
+    assert_eq!(client.request("mFFFE,8"), "00803412");
+    assert_eq!(client.request("MFFFF,4:11112222"), "E01");
+    assert_eq!(client.request("m0,2"), "0000");
+    assert_eq!(client.request("mFFFF,2"), "3412");
+
+    assert_eq!(client.request("D"), "OK");
+    server.join().unwrap();
+}
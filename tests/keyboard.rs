@@ -0,0 +1,48 @@
+use lc3_emu::emulator::KBSR_READY;
+use lc3_emu::{Emulator, MemoryMappedRegister, SharedBuffer, StreamConsole};
+
+use std::io::{self, Read};
+use std::sync::mpsc::{channel, Receiver};
+use std::time::{Duration, Instant};
+
+/// Input that blocks until the test hands it a key.
+struct Pending(Receiver<u8>);
+
+impl Read for Pending {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.0.recv() {
+            Ok(key) => {
+                buf[0] = key;
+                Ok(1)
+            }
+            Err(_) => Ok(0),
+        }
+    }
+}
+
+const KBSR: u16 = MemoryMappedRegister::Kbsr as u16;
+const KBDR: u16 = MemoryMappedRegister::Kbdr as u16;
+
+#[test]
+fn polling_kbsr_without_a_key_does_not_block() {
+    let (keys, input) = channel();
+    let mut emu = Emulator::new();
+    emu.set_console(StreamConsole::new(Pending(input), SharedBuffer::new()));
+
+    let start = Instant::now();
+    for _ in 0..100 {
+        assert_eq!(emu.read_memory(KBSR) & KBSR_READY, 0);
+    }
+    assert!(start.elapsed() < Duration::from_secs(1));
+    assert!(!emu.memory.input_exhausted());
+
+    keys.send(b'a').unwrap();
+    while emu.read_memory(KBSR) & KBSR_READY == 0 {
+        assert!(
+            start.elapsed() < Duration::from_secs(5),
+            "key never arrived"
+        );
+    }
+    assert_eq!(emu.read_memory(KBDR), b'a' as u16);
+    assert_eq!(emu.read_memory(KBSR) & KBSR_READY, 0);
+}
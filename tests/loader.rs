@@ -0,0 +1,215 @@
+use lc3_emu::{
+    check_overlaps, BufferConsole, Emulator, EmulatorError, Format, Image, LoadError, Register,
+};
+
+fn object(origin: u16, words: &[u16]) -> Vec<u8> {
+    let mut bytes = origin.to_be_bytes().to_vec();
+    for word in words {
+        bytes.extend_from_slice(&word.to_be_bytes());
+    }
+    bytes
+}
+
+fn load(bytes: &[u8]) -> Result<Emulator, EmulatorError> {
+    let mut emu = Emulator::new();
+    emu.load_image(bytes)?;
+    Ok(emu)
+}
+
+#[test]
+fn loads_at_x3000() {
+    let emu = load(&object(0x3000, &[0x1234, 0xF025])).unwrap();
+    assert_eq!(emu.memory.peek(0x3000), 0x1234);
+    assert_eq!(emu.memory.peek(0x3001), 0xF025);
+    assert_eq!(emu.register(Register::Rpc), 0x3000);
+}
+
+#[test]
+fn loads_at_x4000_and_starts_there() {
+    let emu = load(&object(0x4000, &[0xABCD, 0x0001])).unwrap();
+    assert_eq!(emu.memory.peek(0x4000), 0xABCD);
+    assert_eq!(emu.memory.peek(0x4001), 0x0001);
+    assert_eq!(emu.memory.peek(0x3000), 0x0000);
+    assert_eq!(emu.register(Register::Rpc), 0x4000);
+}
+
+#[test]
+fn loads_up_to_the_last_word_of_memory() {
+    let emu = load(&object(0xFFFD, &[0x1111, 0x2222, 0x3333])).unwrap();
+    assert_eq!(emu.memory.peek(0xFFFD), 0x1111);
+    assert_eq!(emu.memory.peek(0xFFFF), 0x3333);
+    assert_eq!(emu.register(Register::Rpc), 0xFFFD);
+}
+
+#[test]
+fn rejects_images_past_the_end_of_memory() {
+    let err = Image::from_object_bytes(&object(0xFFFE, &[1, 2, 3])).unwrap_err();
+    assert!(matches!(
+        err,
+        LoadError::OutOfBounds {
+            origin: 0xFFFE,
+            words: 3
+        }
+    ));
+}
+
+#[test]
+fn rejects_truncated_images() {
+    assert!(matches!(
+        Image::from_object_bytes(&[]),
+        Err(LoadError::Truncated)
+    ));
+    assert!(matches!(
+        Image::from_object_bytes(&[0x30]),
+        Err(LoadError::Truncated)
+    ));
+}
+
+#[test]
+fn rejects_odd_length_images() {
+    let mut bytes = object(0x3000, &[0x1234]);
+    bytes.push(0x56);
+    assert!(matches!(
+        load(&bytes),
+        Err(EmulatorError::Load(LoadError::OddLength(5)))
+    ));
+}
+
+#[test]
+fn origin_only_image_is_empty() {
+    let image = Image::from_object_bytes(&object(0x3000, &[])).unwrap();
+    assert_eq!(image.origin, 0x3000);
+    assert!(image.words.is_empty());
+    assert_eq!(image.end(), 0x3000);
+}
+
+#[test]
+fn loads_several_images_and_starts_at_the_first() {
+    let program = Image::new(0x3000, vec![0xF025]).unwrap();
+    let data = Image::new(0x4000, vec![1, 2, 3]).unwrap();
+    let mut emu = Emulator::new();
+    emu.load_images(&[program, data], None).unwrap();
+    assert_eq!(emu.memory.peek(0x3000), 0xF025);
+    assert_eq!(emu.memory.peek(0x4002), 3);
+    assert_eq!(emu.register(Register::Rpc), 0x3000);
+}
+
+#[test]
+fn entry_overrides_the_first_origin() {
+    let data = Image::new(0x4000, vec![1, 2, 3]).unwrap();
+    let program = Image::new(0x3000, vec![0xF025]).unwrap();
+    let mut emu = Emulator::new();
+    emu.load_images(&[data, program], Some(0x3000)).unwrap();
+    assert_eq!(emu.register(Register::Rpc), 0x3000);
+}
+
+#[test]
+fn overlapping_images_are_rejected() {
+    let images = [
+        Image::new(0x3000, vec![0; 0x10]).unwrap(),
+        Image::new(0x4000, vec![0; 4]).unwrap(),
+        Image::new(0x300F, vec![0xFFFF; 2]).unwrap(),
+    ];
+    let mut emu = Emulator::new();
+    assert!(matches!(
+        emu.load_images(&images, None),
+        Err(EmulatorError::Load(LoadError::Overlap {
+            first: 0,
+            second: 2,
+            address: 0x300F
+        }))
+    ));
+    assert_eq!(emu.memory.peek(0x3000), 0);
+}
+
+#[test]
+fn adjacent_images_do_not_overlap() {
+    let images = [
+        Image::new(0x3000, vec![0; 0x10]).unwrap(),
+        Image::new(0x3010, vec![0; 0x10]).unwrap(),
+    ];
+    assert!(check_overlaps(&images).is_ok());
+}
+
+#[test]
+fn parses_ascii_hex() {
+    let text = b"; program\nx3000\n1234\n0xF025\n\nabcd ; data\n";
+    let image = Image::parse(text, Format::Hex, None).unwrap();
+    assert_eq!(
+        image,
+        Image::new(0x3000, vec![0x1234, 0xF025, 0xABCD]).unwrap()
+    );
+}
+
+#[test]
+fn parses_ascii_binary() {
+    let text = b"0011000000000000\n1111000000100101\n";
+    let image = Image::parse(text, Format::Bin, None).unwrap();
+    assert_eq!(image, Image::new(0x3000, vec![0xF025]).unwrap());
+}
+
+#[test]
+fn reports_the_bad_line_of_a_text_image() {
+    let err = Image::parse(b"3000\n12345\n", Format::Hex, None).unwrap_err();
+    assert!(matches!(err, LoadError::Syntax { line: 2, .. }));
+    let err = Image::parse(b"001100000000000\n", Format::Bin, None).unwrap_err();
+    assert!(matches!(err, LoadError::Syntax { line: 1, .. }));
+}
+
+#[test]
+fn raw_images_load_at_an_explicit_origin() {
+    let le = Image::parse(&[0x25, 0xF0, 0x34, 0x12], Format::RawLe, Some(0x4000)).unwrap();
+    assert_eq!(le, Image::new(0x4000, vec![0xF025, 0x1234]).unwrap());
+    let be = Image::parse(&[0xF0, 0x25, 0x12, 0x34], Format::RawBe, Some(0x4000)).unwrap();
+    assert_eq!(be, Image::new(0x4000, vec![0xF025, 0x1234]).unwrap());
+}
+
+#[test]
+fn only_raw_images_take_an_origin() {
+    assert!(matches!(
+        Image::parse(&[0xF0, 0x25], Format::RawBe, None),
+        Err(LoadError::MissingOrigin)
+    ));
+    assert!(matches!(
+        Image::parse(&[0x30, 0x00, 0xF0, 0x25], Format::Object, Some(0x4000)),
+        Err(LoadError::UnexpectedOrigin)
+    ));
+    assert!(matches!(
+        Image::parse(b"x3000\nF025\n", Format::Hex, Some(0x4000)),
+        Err(LoadError::UnexpectedOrigin)
+    ));
+    assert!(Format::Object.has_origin() && Format::Hex.has_origin() && Format::Bin.has_origin());
+    assert!(!Format::RawLe.has_origin() && !Format::RawBe.has_origin());
+}
+
+#[test]
+fn formats_are_detected_from_the_extension() {
+    use std::path::Path;
+    assert_eq!(Format::from_path(Path::new("a.hex")), Format::Hex);
+    assert_eq!(Format::from_path(Path::new("a.BIN")), Format::Bin);
+    assert_eq!(Format::from_path(Path::new("a.obj")), Format::Object);
+    assert_eq!("raw-le".parse(), Ok(Format::RawLe));
+}
+
+#[test]
+fn loading_over_the_device_page_leaves_the_devices_alone() {
+    let console = BufferConsole::new(b"");
+    let output = console.output();
+    let mut emu = Emulator::new();
+    emu.set_console(console);
+
+    // DDR would print 'A' and a zero MCR would stop the clock
+    let mut words = vec![0x1234; 0x200];
+    words[0x06] = b'A' as u16;
+    words[0x1FE] = 0x0000;
+    let devices = Image::new(0xFE00, words).unwrap();
+    let program = Image::new(0x3000, vec![0xF025]).unwrap();
+    emu.load_images(&[program, devices], None).unwrap();
+
+    assert_eq!(emu.memory.peek(0xFE10), 0x1234);
+    assert_eq!(emu.memory.peek(0xFE04), 0x8000);
+    assert_eq!(emu.memory.peek(0xFFFE), 0x8000);
+    assert_eq!(output.to_string_lossy(), "");
+    emu.run().unwrap();
+    assert_eq!(output.to_string_lossy(), "HALT\n");
+}
@@ -0,0 +1,70 @@
+mod common;
+
+use lc3_emu::{os, Emulator, Register, TrapMode};
+
+const PROGRAM: &str = r#"
+        .ORIG x3000
+        GETC
+        OUT
+        LEA R0, TEXT
+        PUTS
+        LEA R0, PACKED
+        PUTSP
+        IN
+        HALT
+TEXT    .STRINGZ "hi"
+PACKED  .FILL x6261
+        .FILL x0063
+        .FILL x0000
+        .END
+"#;
+
+/// Steps until the PC reaches `pc`, as it does when a service routine
+/// returns to the program.
+fn run_to(emu: &mut Emulator, pc: u16) {
+    for _ in 0..10_000 {
+        if emu.register(Register::Rpc) == pc {
+            return;
+        }
+        emu.step().unwrap();
+    }
+    panic!("never reached x{:04X}", pc);
+}
+
+#[test]
+fn os_traps_preserve_registers() {
+    let (mut emu, output) = common::load(PROGRAM, b"km");
+    os::install(&mut emu);
+    assert_eq!(emu.trap_mode, TrapMode::Os);
+
+    let saved = [1, 2, 3, 4, 5, 6].map(|r| (Register::general(r), 0x1111 * r));
+    for (reg, value) in saved {
+        emu.set_register(reg, value);
+    }
+    let text = emu.symbols.address_of("TEXT").unwrap();
+    let packed = emu.symbols.address_of("PACKED").unwrap();
+
+    // the PC after each TRAP and what R0 holds once the routine returns
+    let traps = [
+        (0x3001, b'k' as u16),
+        (0x3002, b'k' as u16),
+        (0x3004, text),
+        (0x3006, packed),
+        (0x3007, b'm' as u16),
+    ];
+    for (pc, r0) in traps {
+        run_to(&mut emu, pc);
+        assert_eq!(emu.register(Register::Rr0), r0, "after x{:04X}", pc - 1);
+        assert_eq!(emu.register(Register::Rr7), pc, "after x{:04X}", pc - 1);
+        for (reg, value) in saved {
+            assert_eq!(emu.register(reg), value, "{:?} after x{:04X}", reg, pc - 1);
+        }
+    }
+
+    emu.run().unwrap();
+    assert!(!emu.running);
+    assert_eq!(
+        output.to_string_lossy(),
+        "khiabcEnter a character: m\nHALT\n"
+    );
+}
@@ -0,0 +1,25 @@
+use lc3_emu::disassembler::disassemble;
+use lc3_emu::SymbolTable;
+
+fn table() -> SymbolTable {
+    let mut symbols = SymbolTable::new();
+    symbols.insert("MAIN", 0x3000);
+    symbols.insert("LOOP", 0x300F);
+    symbols
+}
+
+#[test]
+fn addresses_are_named_after_the_nearest_preceding_label() {
+    let symbols = table();
+    assert_eq!(symbols.describe(0x3000), "MAIN");
+    assert_eq!(symbols.describe(0x3002), "MAIN+2");
+    assert_eq!(symbols.describe(0x3012), "LOOP+3");
+    assert_eq!(symbols.describe(0x2FFF), "x2FFF");
+    assert_eq!(symbols.describe(0x4000), "x4000");
+}
+
+#[test]
+fn disassembly_names_targets_with_offsets() {
+    // BRnzp #1 at LOOP+1 lands on LOOP+3
+    assert_eq!(disassemble(0x3010, 0x0E01, Some(&table())), "BRnzp LOOP+3");
+}
@@ -0,0 +1,24 @@
+use lc3_emu::terminal::is_tty;
+use lc3_emu::TerminalGuard;
+
+use termios::Termios;
+
+#[test]
+fn disabled_guard_never_enables_raw_mode() {
+    let guard = TerminalGuard::disabled();
+    assert!(!guard.is_enabled());
+    drop(guard);
+}
+
+#[test]
+fn raw_guard_leaves_the_terminal_as_it_found_it() {
+    // without a terminal, as under CI, both reads fail and the guard is
+    // disabled; with one, dropping the guard restores the settings
+    let before = Termios::from_fd(0).ok();
+    for _ in 0..2 {
+        let guard = TerminalGuard::raw().unwrap();
+        assert_eq!(guard.is_enabled(), is_tty());
+        drop(guard);
+        assert_eq!(Termios::from_fd(0).ok(), before);
+    }
+}
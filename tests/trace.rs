@@ -0,0 +1,50 @@
+use lc3_emu::assembler::assemble;
+use lc3_emu::{BufferConsole, Emulator, SharedBuffer, Tracer};
+
+const PROGRAM: &str = r#"
+        .ORIG x3000
+MAIN    AND R1, R1, #0
+        ADD R1, R1, #2
+LOOP    ADD R1, R1, #-1
+        STI R1, PTR
+        BRp LOOP
+        HALT
+PTR     .FILL x4000
+        .END
+"#;
+
+fn trace(tracer: impl FnOnce(Tracer) -> Tracer) -> Vec<String> {
+    let program = assemble(PROGRAM).unwrap();
+    let log = SharedBuffer::new();
+
+    let mut emu = Emulator::new();
+    emu.set_console(BufferConsole::new(b""));
+    emu.load_image(&program.to_object_bytes()[..]).unwrap();
+    emu.symbols = program.symbols;
+    emu.tracer = Some(tracer(Tracer::new(log.clone())));
+    emu.run().unwrap();
+
+    log.to_string_lossy().lines().map(str::to_string).collect()
+}
+
+#[test]
+fn every_instruction_is_traced_with_its_effects() {
+    let lines = trace(|tracer| tracer);
+    assert_eq!(lines.len(), 9);
+    assert!(lines[0].starts_with("x3000  x5260  MAIN"));
+    assert!(lines[0].ends_with("R1=x0000 NZP=Z"));
+    assert!(lines[2].contains("ADD R1, R1, #-1"));
+    assert!(lines[2].ends_with("R1=x0001 NZP=P"));
+    assert!(lines[3].ends_with("read x3006=x4000 write x4000=x0001 NZP=P"));
+}
+
+#[test]
+fn traces_can_be_filtered_and_capped() {
+    let lines = trace(|tracer| tracer.with_range(0x3002..=0x3002));
+    assert_eq!(lines.len(), 2);
+    assert!(lines.iter().all(|line| line.starts_with("x3002")));
+
+    let lines = trace(|tracer| tracer.with_limit(3));
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[3], "Trace limit of 3 entries reached");
+}